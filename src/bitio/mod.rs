@@ -0,0 +1,350 @@
+use std::error::Error;
+use std::fmt;
+
+/// How bits are packed into bytes. `Msb` fills each byte from its most
+/// significant bit down and writes multi-bit values high bit first; `Lsb`
+/// fills bytes from bit 0 up and writes values low bit first, as DEFLATE does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    Msb,
+    Lsb,
+}
+
+/// Unary codes, and so Rice quotients, hold fewer than this many one bits.
+pub const MAX_UNARY: u64 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitIoError {
+    /// The input ended before the requested bits.
+    UnexpectedEof,
+    /// A variable-length code does not fit in 64 bits.
+    Overflow,
+}
+
+impl fmt::Display for BitIoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BitIoError::UnexpectedEof => write!(f, "unexpected end of bit stream"),
+            BitIoError::Overflow => write!(f, "variable-length code overflows 64 bits"),
+        }
+    }
+}
+
+impl Error for BitIoError {}
+
+fn mask(n: u32) -> u64 {
+    if n >= 64 { !0 } else { (1u64 << n) - 1 }
+}
+
+#[derive(Debug)]
+pub struct BitWriter {
+    buf: Vec<u8>,
+    acc: u64,
+    nbits: u32,
+    order: BitOrder,
+}
+
+impl BitWriter {
+    pub fn new() -> BitWriter {
+        BitWriter::with_order(BitOrder::Msb)
+    }
+
+    pub fn with_order(order: BitOrder) -> BitWriter {
+        BitWriter {
+            buf: Vec::new(),
+            acc: 0,
+            nbits: 0,
+            order,
+        }
+    }
+
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Number of bits written so far, including those not yet flushed.
+    pub fn bit_len(&self) -> usize {
+        self.buf.len() * 8 + self.nbits as usize
+    }
+
+    // Appends at most 32 bits; the accumulator never holds more than 39.
+    fn push_chunk(&mut self, chunk: u64, n: u32) {
+        match self.order {
+            BitOrder::Msb => {
+                self.acc = (self.acc << n) | chunk;
+                self.nbits += n;
+                while self.nbits >= 8 {
+                    self.nbits -= 8;
+                    self.buf.push((self.acc >> self.nbits) as u8);
+                }
+            }
+            BitOrder::Lsb => {
+                self.acc |= chunk << self.nbits;
+                self.nbits += n;
+                while self.nbits >= 8 {
+                    self.buf.push(self.acc as u8);
+                    self.acc >>= 8;
+                    self.nbits -= 8;
+                }
+            }
+        }
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        self.push_chunk(bit as u64, 1);
+    }
+
+    /// Writes the low `n` bits of `value`, `n <= 64`.
+    pub fn write_bits(&mut self, value: u64, n: u32) {
+        assert!(n <= 64, "cannot write more than 64 bits at once");
+        let value = value & mask(n);
+        match self.order {
+            BitOrder::Msb => {
+                let mut left = n;
+                while left > 0 {
+                    let c = left.min(32);
+                    self.push_chunk((value >> (left - c)) & mask(c), c);
+                    left -= c;
+                }
+            }
+            BitOrder::Lsb => {
+                let mut done = 0;
+                while done < n {
+                    let c = (n - done).min(32);
+                    self.push_chunk((value >> done) & mask(c), c);
+                    done += c;
+                }
+            }
+        }
+    }
+
+    /// Writes `n` in unary: `n` one bits and a terminating zero. `n` must
+    /// be below `MAX_UNARY`, the limit `read_unary` accepts.
+    pub fn write_unary(&mut self, n: u64) {
+        assert!(n < MAX_UNARY, "unary codes are limited to {} ones", MAX_UNARY - 1);
+        for _ in 0..n {
+            self.write_bit(true);
+        }
+        self.write_bit(false);
+    }
+
+    // Writes the low `n` bits of `value` high bit first whatever the packing
+    // order, which is how the universal codes below are defined.
+    fn write_msb_first(&mut self, value: u64, n: u32) {
+        for i in (0..n).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    /// Elias gamma code of `n >= 1`.
+    pub fn write_gamma(&mut self, n: u64) {
+        assert!(n >= 1, "elias gamma codes start at 1");
+        let bits = 64 - n.leading_zeros();
+        for _ in 1..bits {
+            self.write_bit(false);
+        }
+        self.write_msb_first(n, bits);
+    }
+
+    /// Elias delta code of `n >= 1`.
+    pub fn write_delta(&mut self, n: u64) {
+        assert!(n >= 1, "elias delta codes start at 1");
+        let bits = 64 - n.leading_zeros();
+        self.write_gamma(bits as u64);
+        self.write_msb_first(n, bits - 1);
+    }
+
+    /// Golomb-Rice code of `n` with parameter `k`: the quotient `n >> k` in
+    /// unary followed by the low `k` bits. The quotient must be below
+    /// `MAX_UNARY`.
+    pub fn write_rice(&mut self, n: u64, k: u32) {
+        assert!(k < 64, "rice parameter must be below 64");
+        assert!(n >> k < MAX_UNARY, "rice quotient of {} with k = {} is too large", n, k);
+        self.write_unary(n >> k);
+        self.write_msb_first(n & mask(k), k);
+    }
+
+    /// Pads the last partial byte with zero bits.
+    pub fn flush(&mut self) {
+        if self.nbits > 0 {
+            let byte = match self.order {
+                BitOrder::Msb => (self.acc << (8 - self.nbits)) as u8,
+                BitOrder::Lsb => self.acc as u8,
+            };
+            self.buf.push(byte);
+            self.acc = 0;
+            self.nbits = 0;
+        }
+    }
+
+    /// Drains the bytes completed so far; a trailing partial byte stays
+    /// until more bits arrive or `flush` is called.
+    pub fn data(&mut self) -> Vec<u8> {
+        self.buf.drain(..).collect()
+    }
+
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.flush();
+        self.buf
+    }
+}
+
+pub struct BitReader<'a> {
+    buf: &'a [u8],
+    ptr: usize,
+    acc: u64,
+    nbits: u32,
+    order: BitOrder,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buf: &'a [u8]) -> BitReader<'a> {
+        BitReader::with_order(buf, BitOrder::Msb)
+    }
+
+    pub fn with_order(buf: &'a [u8], order: BitOrder) -> BitReader<'a> {
+        BitReader {
+            buf,
+            ptr: 0,
+            acc: 0,
+            nbits: 0,
+            order,
+        }
+    }
+
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    pub fn bits_remaining(&self) -> usize {
+        (self.buf.len() - self.ptr) * 8 + self.nbits as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits_remaining() == 0
+    }
+
+    fn refill(&mut self) {
+        while self.nbits <= 56 && self.ptr < self.buf.len() {
+            let byte = self.buf[self.ptr] as u64;
+            match self.order {
+                BitOrder::Msb => self.acc |= byte << (56 - self.nbits),
+                BitOrder::Lsb => self.acc |= byte << self.nbits,
+            }
+            self.ptr += 1;
+            self.nbits += 8;
+        }
+    }
+
+    /// Looks at the next `n <= 32` bits without consuming them. Bits past
+    /// the end of the input read as zero; check `bits_remaining` to tell.
+    pub fn peek_bits(&mut self, n: u32) -> u64 {
+        assert!(n <= 32, "cannot peek more than 32 bits");
+        if n == 0 {
+            return 0;
+        }
+        self.refill();
+        match self.order {
+            BitOrder::Msb => self.acc >> (64 - n),
+            BitOrder::Lsb => self.acc & mask(n),
+        }
+    }
+
+    /// Discards `n <= 32` bits.
+    pub fn skip_bits(&mut self, n: u32) -> Result<(), BitIoError> {
+        assert!(n <= 32, "cannot skip more than 32 bits at once");
+        self.refill();
+        if n > self.nbits {
+            return Err(BitIoError::UnexpectedEof);
+        }
+        match self.order {
+            BitOrder::Msb => self.acc <<= n,
+            BitOrder::Lsb => self.acc >>= n,
+        }
+        self.nbits -= n;
+        Ok(())
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, BitIoError> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Reads `n <= 64` bits into the low bits of the result. On error
+    /// nothing is consumed.
+    pub fn read_bits(&mut self, n: u32) -> Result<u64, BitIoError> {
+        assert!(n <= 64, "cannot read more than 64 bits at once");
+        if (n as usize) > self.bits_remaining() {
+            return Err(BitIoError::UnexpectedEof);
+        }
+        let mut value = 0u64;
+        let mut done = 0;
+        while done < n {
+            let c = (n - done).min(32);
+            let chunk = self.peek_bits(c);
+            self.skip_bits(c)?;
+            match self.order {
+                BitOrder::Msb => value = (value << c) | chunk,
+                BitOrder::Lsb => value |= chunk << done,
+            }
+            done += c;
+        }
+        Ok(value)
+    }
+
+    /// Drops the bits left in the current byte.
+    pub fn align_to_byte(&mut self) {
+        let n = self.nbits % 8;
+        self.skip_bits(n).expect("partial byte is always buffered");
+    }
+
+    pub fn read_unary(&mut self) -> Result<u64, BitIoError> {
+        let mut n = 0;
+        while self.read_bit()? {
+            n += 1;
+            if n == MAX_UNARY {
+                return Err(BitIoError::Overflow);
+            }
+        }
+        Ok(n)
+    }
+
+    fn read_msb_first(&mut self, n: u32) -> Result<u64, BitIoError> {
+        let mut value = 0u64;
+        for _ in 0..n {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+
+    pub fn read_gamma(&mut self) -> Result<u64, BitIoError> {
+        let mut zeros = 0;
+        while !self.read_bit()? {
+            zeros += 1;
+            if zeros == 64 {
+                return Err(BitIoError::Overflow);
+            }
+        }
+        Ok((1 << zeros) | self.read_msb_first(zeros)?)
+    }
+
+    pub fn read_delta(&mut self) -> Result<u64, BitIoError> {
+        let bits = self.read_gamma()?;
+        if bits > 64 {
+            return Err(BitIoError::Overflow);
+        }
+        let low = self.read_msb_first(bits as u32 - 1)?;
+        Ok((1 << (bits - 1)) | low)
+    }
+
+    pub fn read_rice(&mut self, k: u32) -> Result<u64, BitIoError> {
+        assert!(k < 64, "rice parameter must be below 64");
+        let q = self.read_unary()?;
+        if k > 0 && q > (!0u64 >> k) {
+            return Err(BitIoError::Overflow);
+        }
+        Ok((q << k) | self.read_msb_first(k)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
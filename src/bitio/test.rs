@@ -0,0 +1,160 @@
+use std::iter;
+
+use super::{BitWriter, BitReader, BitOrder, BitIoError, MAX_UNARY};
+
+#[test]
+fn test_read_bits() {
+    let data = vec![12u8];
+    let mut reader = BitReader::new(&data);
+
+    let results: Vec<_> = iter::repeat(0).map(|_| reader.read_bit().unwrap()).take(8).collect();
+    assert_eq!(results, vec![false, false, false, false, true, true, false, false]);
+    assert_eq!(reader.read_bit(), Err(BitIoError::UnexpectedEof));
+}
+
+#[test]
+fn test_write_bits() {
+    let mut writer = BitWriter::new();
+
+    for _ in 0..4 {
+        writer.write_bit(false);
+    }
+    for _ in 0..4 {
+        writer.write_bit(true);
+    }
+    for _ in 0..4 {
+        writer.write_bit(false);
+    }
+    writer.write_bit(true);
+    writer.write_bit(true);
+
+    writer.flush();
+    assert_eq!(writer.data(), vec![15u8, 12u8]);
+}
+
+#[test]
+fn test_multi_bit_msb() {
+    let mut writer = BitWriter::new();
+    writer.write_bits(0b101, 3);
+    writer.write_bits(0x1234_5678_9abc_def0, 64);
+    writer.write_bits(0b11, 2);
+    assert_eq!(writer.bit_len(), 69);
+    let data = writer.into_bytes();
+    assert_eq!(data[0], 0b1010_0010);
+
+    let mut reader = BitReader::new(&data);
+    assert_eq!(reader.read_bits(3), Ok(0b101));
+    assert_eq!(reader.read_bits(64), Ok(0x1234_5678_9abc_def0));
+    assert_eq!(reader.read_bits(2), Ok(0b11));
+    assert_eq!(reader.bits_remaining(), 3);
+    assert_eq!(reader.read_bits(4), Err(BitIoError::UnexpectedEof));
+    assert_eq!(reader.read_bits(3), Ok(0));
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_multi_bit_lsb() {
+    let mut writer = BitWriter::with_order(BitOrder::Lsb);
+    writer.write_bits(0b101, 3);
+    writer.write_bits(0b11110, 5);
+    writer.write_bits(0xabcd, 16);
+    writer.write_bit(true);
+    let data = writer.into_bytes();
+    assert_eq!(data, vec![0b1111_0101, 0xcd, 0xab, 0x01]);
+
+    let mut reader = BitReader::with_order(&data, BitOrder::Lsb);
+    assert_eq!(reader.peek_bits(3), 0b101);
+    assert_eq!(reader.read_bits(3), Ok(0b101));
+    assert_eq!(reader.read_bits(5), Ok(0b11110));
+    assert_eq!(reader.read_bits(16), Ok(0xabcd));
+    assert_eq!(reader.read_bit(), Ok(true));
+    reader.align_to_byte();
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_align_to_byte() {
+    let data = vec![0xff, 0x42];
+    let mut reader = BitReader::new(&data);
+    assert_eq!(reader.read_bits(3), Ok(0b111));
+    reader.align_to_byte();
+    assert_eq!(reader.read_bits(8), Ok(0x42));
+}
+
+#[test]
+fn test_gamma_delta() {
+    let values = [1u64, 2, 3, 4, 5, 17, 1000, 1 << 40, !0];
+
+    for &order in &[BitOrder::Msb, BitOrder::Lsb] {
+        let mut writer = BitWriter::with_order(order);
+        for &value in &values {
+            writer.write_gamma(value);
+            writer.write_delta(value);
+        }
+        let data = writer.into_bytes();
+
+        let mut reader = BitReader::with_order(&data, order);
+        for &value in &values {
+            assert_eq!(reader.read_gamma(), Ok(value));
+            assert_eq!(reader.read_delta(), Ok(value));
+        }
+    }
+
+    // gamma(4) = 00100, gamma(1) = 1
+    let mut writer = BitWriter::new();
+    writer.write_gamma(4);
+    writer.write_gamma(1);
+    assert_eq!(writer.into_bytes(), vec![0b0010_0100]);
+}
+
+#[test]
+fn test_rice() {
+    let mut writer = BitWriter::new();
+    for n in 0..100 {
+        writer.write_rice(n, 3);
+    }
+    writer.write_rice(9, 2);
+    let data = writer.into_bytes();
+
+    let mut reader = BitReader::new(&data);
+    for n in 0..100 {
+        assert_eq!(reader.read_rice(3), Ok(n));
+    }
+    // 9 = 0b10 << 2 | 0b01: unary 110 then 01.
+    assert_eq!(reader.read_bits(5), Ok(0b11001));
+}
+
+#[test]
+fn test_code_errors() {
+    let zeros = vec![0u8; 16];
+    assert_eq!(BitReader::new(&zeros).read_gamma(), Err(BitIoError::Overflow));
+    assert_eq!(BitReader::new(&zeros[..2]).read_gamma(), Err(BitIoError::UnexpectedEof));
+
+    let ones = vec![0xffu8; 16];
+    assert_eq!(BitReader::new(&ones).read_unary(), Err(BitIoError::Overflow));
+    assert_eq!(BitReader::new(&ones[..1]).read_rice(2), Err(BitIoError::UnexpectedEof));
+}
+
+#[test]
+fn test_unary_limit() {
+    let mut writer = BitWriter::new();
+    writer.write_unary(MAX_UNARY - 1);
+    writer.write_rice(((MAX_UNARY - 1) << 5) | 0b10101, 5);
+    let data = writer.into_bytes();
+
+    let mut reader = BitReader::new(&data);
+    assert_eq!(reader.read_unary(), Ok(MAX_UNARY - 1));
+    assert_eq!(reader.read_rice(5), Ok(((MAX_UNARY - 1) << 5) | 0b10101));
+}
+
+#[test]
+#[should_panic]
+fn test_unary_too_long() {
+    BitWriter::new().write_unary(MAX_UNARY);
+}
+
+#[test]
+#[should_panic]
+fn test_rice_quotient_too_large() {
+    BitWriter::new().write_rice(u64::MAX, 0);
+}
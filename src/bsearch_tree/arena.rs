@@ -0,0 +1,149 @@
+use std::cmp::Ordering;
+use std::ops::{Index, IndexMut};
+
+use slab::Slab;
+
+// Node of an arena-backed map. Children are slab indices; `meta` holds
+// whatever the balancing scheme needs (height, color, ...).
+pub struct Node<K, V, M> {
+    pub key: K,
+    pub value: V,
+    pub meta: M,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+impl<K, V, M> Node<K, V, M> {
+    pub fn new(key: K, value: V, meta: M) -> Node<K, V, M> {
+        Node {
+            key,
+            value,
+            meta,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+pub struct Arena<K, V, M> {
+    nodes: Slab<Node<K, V, M>>,
+}
+
+impl<K, V, M> Arena<K, V, M> {
+    pub fn new() -> Arena<K, V, M> {
+        Arena {
+            nodes: Slab::with_capacity(16),
+        }
+    }
+
+    pub fn insert(&mut self, node: Node<K, V, M>) -> usize {
+        match self.nodes.insert(node) {
+            Ok(idx) => idx,
+            Err(node) => {
+                let capacity = self.nodes.capacity();
+                self.nodes.reserve_exact(capacity.max(16));
+                match self.nodes.insert(node) {
+                    Ok(idx) => idx,
+                    Err(_) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Node<K, V, M> {
+        self.nodes.remove(idx).expect("invalid node index")
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    pub fn find(&self, mut tree: Option<usize>, key: &K) -> Option<usize>
+        where K: Ord {
+        while let Some(n) = tree {
+            tree = match key.cmp(&self[n].key) {
+                Ordering::Less => self[n].left,
+                Ordering::Greater => self[n].right,
+                Ordering::Equal => return Some(n),
+            };
+        }
+        None
+    }
+
+    pub fn iter(&self, root: Option<usize>) -> MapIter<'_, K, V, M> {
+        let mut iter = MapIter {
+            arena: self,
+            stack: Vec::new(),
+        };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    pub fn keys(&self, root: Option<usize>) -> Keys<'_, K, V, M> {
+        Keys(self.iter(root))
+    }
+
+    pub fn values(&self, root: Option<usize>) -> Values<'_, K, V, M> {
+        Values(self.iter(root))
+    }
+}
+
+impl<K, V, M> Index<usize> for Arena<K, V, M> {
+    type Output = Node<K, V, M>;
+
+    fn index(&self, idx: usize) -> &Node<K, V, M> {
+        self.nodes.get(idx).expect("invalid node index")
+    }
+}
+
+impl<K, V, M> IndexMut<usize> for Arena<K, V, M> {
+    fn index_mut(&mut self, idx: usize) -> &mut Node<K, V, M> {
+        self.nodes.get_mut(idx).expect("invalid node index")
+    }
+}
+
+/// In-order iterator over the entries of an arena-backed map.
+pub struct MapIter<'a, K: 'a, V: 'a, M: 'a> {
+    arena: &'a Arena<K, V, M>,
+    stack: Vec<usize>,
+}
+
+impl<'a, K, V, M> MapIter<'a, K, V, M> {
+    fn push_left_spine(&mut self, mut tree: Option<usize>) {
+        while let Some(n) = tree {
+            self.stack.push(n);
+            tree = self.arena[n].left;
+        }
+    }
+}
+
+impl<'a, K, V, M> Iterator for MapIter<'a, K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let n = self.stack.pop()?;
+        let arena = self.arena;
+        self.push_left_spine(arena[n].right);
+        Some((&arena[n].key, &arena[n].value))
+    }
+}
+
+pub struct Keys<'a, K: 'a, V: 'a, M: 'a>(MapIter<'a, K, V, M>);
+
+impl<'a, K, V, M> Iterator for Keys<'a, K, V, M> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(k, _)| k)
+    }
+}
+
+pub struct Values<'a, K: 'a, V: 'a, M: 'a>(MapIter<'a, K, V, M>);
+
+impl<'a, K, V, M> Iterator for Values<'a, K, V, M> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.0.next().map(|(_, v)| v)
+    }
+}
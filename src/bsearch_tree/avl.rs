@@ -0,0 +1,345 @@
+use std::cmp::{self, Ordering};
+use std::mem;
+
+use super::arena::{Arena, Node, MapIter, Keys, Values};
+
+// `meta` is the height of the subtree rooted at the node.
+type AvlNode<K, V> = Node<K, V, usize>;
+
+/// Per-node data of an arena-backed AVL tree. Besides the subtree height,
+/// which balancing needs, it may aggregate anything that can be recomputed
+/// from a node and the data of its two children.
+pub trait Augment<K, V>: Sized {
+    fn compute(key: &K, value: &V, left: Option<&Self>, right: Option<&Self>) -> Self;
+
+    fn height(&self) -> usize;
+}
+
+// A plain AVL tree only tracks heights.
+impl<K, V> Augment<K, V> for usize {
+    fn compute(_: &K, _: &V, left: Option<&usize>, right: Option<&usize>) -> usize {
+        1 + cmp::max(left.map_or(0, |&h| h), right.map_or(0, |&h| h))
+    }
+
+    fn height(&self) -> usize {
+        *self
+    }
+}
+
+// AVL balancing shared by every arena-backed tree whose `meta` implements
+// `Augment`. Rotations recompute the data of the nodes they move.
+impl<K, V, M: Augment<K, V>> Arena<K, V, M> {
+    pub fn insert_leaf(&mut self, key: K, value: V) -> usize {
+        let meta = M::compute(&key, &value, None, None);
+        self.insert(Node::new(key, value, meta))
+    }
+
+    pub fn meta_of(&self, tree: Option<usize>) -> Option<&M> {
+        tree.map(|n| &self[n].meta)
+    }
+
+    pub fn height_of(&self, tree: Option<usize>) -> usize {
+        self.meta_of(tree).map_or(0, |meta| meta.height())
+    }
+
+    fn update(&mut self, n: usize) {
+        let meta = {
+            let node = &self[n];
+            M::compute(&node.key, &node.value, self.meta_of(node.left), self.meta_of(node.right))
+        };
+        self[n].meta = meta;
+    }
+
+    fn balance_factor(&self, n: usize) -> isize {
+        self.height_of(self[n].left) as isize - self.height_of(self[n].right) as isize
+    }
+
+    fn rotate_right(&mut self, n: usize) -> usize {
+        let l = self[n].left.expect("rotate_right without a left child");
+        self[n].left = self[l].right;
+        self[l].right = Some(n);
+        self.update(n);
+        self.update(l);
+        l
+    }
+
+    fn rotate_left(&mut self, n: usize) -> usize {
+        let r = self[n].right.expect("rotate_left without a right child");
+        self[n].right = self[r].left;
+        self[r].left = Some(n);
+        self.update(n);
+        self.update(r);
+        r
+    }
+
+    /// Refreshes the data of `n` after its children changed and restores
+    /// the AVL property there, returning the new root of the subtree.
+    pub fn rebalance(&mut self, n: usize) -> usize {
+        self.update(n);
+        let balance = self.balance_factor(n);
+        if balance > 1 {
+            let l = self[n].left.unwrap();
+            if self.balance_factor(l) < 0 {
+                self[n].left = Some(self.rotate_left(l));
+            }
+            self.rotate_right(n)
+        } else if balance < -1 {
+            let r = self[n].right.unwrap();
+            if self.balance_factor(r) > 0 {
+                self[n].right = Some(self.rotate_right(r));
+            }
+            self.rotate_left(n)
+        } else {
+            n
+        }
+    }
+
+    /// Takes `n` out of its subtree, returning the balanced subtree that
+    /// replaces it. The node itself stays in the arena for the caller.
+    pub fn unlink(&mut self, n: usize) -> Option<usize> {
+        let (left, right) = (self[n].left, self[n].right);
+        // The in-order successor takes the removed node's place.
+        match right {
+            None => left,
+            Some(r) => {
+                let (right, min) = self.detach_min(r);
+                self[min].left = left;
+                self[min].right = right;
+                Some(self.rebalance(min))
+            }
+        }
+    }
+
+    // Unlinks the minimum node of the subtree rooted at `n`, returning the
+    // new subtree root and the detached node.
+    fn detach_min(&mut self, n: usize) -> (Option<usize>, usize) {
+        match self[n].left {
+            None => (self[n].right, n),
+            Some(l) => {
+                let (left, min) = self.detach_min(l);
+                self[n].left = left;
+                (Some(self.rebalance(n)), min)
+            }
+        }
+    }
+}
+
+/// Ordered map kept balanced by AVL rotations: the heights of the two
+/// subtrees of every node differ by at most one.
+pub struct AvlTreeMap<K, V> {
+    nodes: Arena<K, V, usize>,
+    root: Option<usize>,
+    len: usize,
+}
+
+impl<K: Ord, V> AvlTreeMap<K, V> {
+    pub fn new() -> AvlTreeMap<K, V> {
+        AvlTreeMap {
+            nodes: Arena::new(),
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        self.nodes.height_of(self.root)
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+        self.len = 0;
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.nodes.find(self.root, key).map(|n| &self.nodes[n].value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.nodes.find(self.root, key) {
+            Some(n) => Some(&mut self.nodes[n].value),
+            None => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.nodes.find(self.root, key).is_some()
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let root = self.root;
+        let (root, _, old) = self.insert_at(root, key, value);
+        self.root = Some(root);
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let root = self.root;
+        let (root, removed) = self.remove_at(root, key);
+        self.root = root;
+        removed.map(|node| node.value)
+    }
+
+    pub fn entry(&mut self, key: K) -> AvlEntry<'_, K, V> {
+        let index = self.nodes.find(self.root, &key);
+        AvlEntry {
+            map: self,
+            key,
+            index,
+        }
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> MapIter<'_, K, V, usize> {
+        self.nodes.iter(self.root)
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, usize> {
+        self.nodes.keys(self.root)
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, usize> {
+        self.nodes.values(self.root)
+    }
+
+    // Returns the new subtree root, the index of the node holding `key` and
+    // the replaced value, if any.
+    fn insert_at(&mut self, tree: Option<usize>, key: K, value: V) -> (usize, usize, Option<V>) {
+        let n = match tree {
+            Some(n) => n,
+            None => {
+                let n = self.nodes.insert_leaf(key, value);
+                self.len += 1;
+                return (n, n, None);
+            }
+        };
+        match key.cmp(&self.nodes[n].key) {
+            Ordering::Less => {
+                let left = self.nodes[n].left;
+                let (left, idx, old) = self.insert_at(left, key, value);
+                self.nodes[n].left = Some(left);
+                (self.nodes.rebalance(n), idx, old)
+            },
+            Ordering::Greater => {
+                let right = self.nodes[n].right;
+                let (right, idx, old) = self.insert_at(right, key, value);
+                self.nodes[n].right = Some(right);
+                (self.nodes.rebalance(n), idx, old)
+            },
+            Ordering::Equal => {
+                let old = mem::replace(&mut self.nodes[n].value, value);
+                (n, n, Some(old))
+            }
+        }
+    }
+
+    fn remove_at(&mut self, tree: Option<usize>, key: &K) -> (Option<usize>, Option<AvlNode<K, V>>) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (None, None),
+        };
+        match key.cmp(&self.nodes[n].key) {
+            Ordering::Less => {
+                let left = self.nodes[n].left;
+                let (left, removed) = self.remove_at(left, key);
+                self.nodes[n].left = left;
+                (Some(self.nodes.rebalance(n)), removed)
+            },
+            Ordering::Greater => {
+                let right = self.nodes[n].right;
+                let (right, removed) = self.remove_at(right, key);
+                self.nodes[n].right = right;
+                (Some(self.nodes.rebalance(n)), removed)
+            },
+            Ordering::Equal => {
+                let replacement = self.nodes.unlink(n);
+                self.len -= 1;
+                (replacement, Some(self.nodes.remove(n)))
+            }
+        }
+    }
+
+    /// Panics unless the keys are ordered and every node's stored height
+    /// and balance factor are correct.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        let root = self.root;
+        let (count, _) = self.check_subtree(root, None, None);
+        assert_eq!(count, self.len);
+    }
+
+    #[cfg(test)]
+    fn check_subtree(&self, tree: Option<usize>, lower: Option<&K>, upper: Option<&K>) -> (usize, usize) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (0, 0),
+        };
+        let node = &self.nodes[n];
+        assert!(lower.is_none_or(|lower| *lower < node.key), "keys out of order");
+        assert!(upper.is_none_or(|upper| node.key < *upper), "keys out of order");
+        let (left_count, left_height) = self.check_subtree(node.left, lower, Some(&node.key));
+        let (right_count, right_height) = self.check_subtree(node.right, Some(&node.key), upper);
+        assert!((left_height as isize - right_height as isize).abs() <= 1, "node out of balance");
+        let height = 1 + cmp::max(left_height, right_height);
+        assert_eq!(node.meta, height, "stale height");
+        (1 + left_count + right_count, height)
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a AvlTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = MapIter<'a, K, V, usize>;
+
+    fn into_iter(self) -> MapIter<'a, K, V, usize> {
+        self.iter()
+    }
+}
+
+/// A view into a single key of an `AvlTreeMap`, which may or may not be
+/// present.
+pub struct AvlEntry<'a, K: 'a, V: 'a> {
+    map: &'a mut AvlTreeMap<K, V>,
+    key: K,
+    index: Option<usize>,
+}
+
+impl<'a, K: Ord, V> AvlEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        let map = self.map;
+        let idx = match self.index {
+            Some(idx) => idx,
+            None => {
+                let root = map.root;
+                let (root, idx, _) = map.insert_at(root, self.key, default());
+                map.root = Some(root);
+                idx
+            }
+        };
+        &mut map.nodes[idx].value
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> AvlEntry<'a, K, V> {
+        if let Some(idx) = self.index {
+            f(&mut self.map.nodes[idx].value);
+        }
+        self
+    }
+}
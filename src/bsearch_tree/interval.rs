@@ -0,0 +1,253 @@
+use std::cmp::{self, Ordering};
+
+use super::arena::{Arena, MapIter};
+use super::avl::Augment;
+
+/// Closed interval `[start, end]`. Intervals order by `start`, then `end`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Ord> Interval<T> {
+    pub fn new(start: T, end: T) -> Interval<T> {
+        assert!(start <= end, "interval start must not exceed its end");
+        Interval { start, end }
+    }
+
+    pub fn overlaps(&self, start: &T, end: &T) -> bool {
+        self.start <= *end && *start <= self.end
+    }
+
+    pub fn contains(&self, point: &T) -> bool {
+        self.start <= *point && *point <= self.end
+    }
+}
+
+// Per-node AVL height plus the largest endpoint found in the subtree.
+#[derive(Debug, Clone)]
+pub struct IntervalMeta<T> {
+    height: usize,
+    max_end: T,
+}
+
+impl<T: Ord + Clone, V> Augment<Interval<T>, V> for IntervalMeta<T> {
+    fn compute(key: &Interval<T>, _: &V, left: Option<&IntervalMeta<T>>, right: Option<&IntervalMeta<T>>)
+               -> IntervalMeta<T> {
+        let mut max_end = &key.end;
+        for child in left.iter().chain(right.iter()) {
+            max_end = cmp::max(max_end, &child.max_end);
+        }
+        IntervalMeta {
+            height: 1 + cmp::max(left.map_or(0, |meta| meta.height), right.map_or(0, |meta| meta.height)),
+            max_end: max_end.clone(),
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// AVL tree keyed by interval start and augmented with the maximum end
+/// point of every subtree, so all intervals overlapping a query can be
+/// reported in O(log n + k). The same interval may be stored several times.
+pub struct IntervalTree<T, V> {
+    nodes: Arena<Interval<T>, V, IntervalMeta<T>>,
+    root: Option<usize>,
+    len: usize,
+}
+
+impl<T: Ord + Clone, V> IntervalTree<T, V> {
+    pub fn new() -> IntervalTree<T, V> {
+        IntervalTree {
+            nodes: Arena::new(),
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        self.nodes.height_of(self.root)
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Stores `value` under `[start, end]`; panics if `start > end`.
+    pub fn insert(&mut self, start: T, end: T, value: V) {
+        let root = self.root;
+        self.root = Some(self.insert_at(root, Interval::new(start, end), value));
+        self.len += 1;
+    }
+
+    /// Removes one entry stored under exactly `[start, end]`.
+    pub fn remove(&mut self, start: &T, end: &T) -> Option<V> {
+        let interval = Interval {
+            start: start.clone(),
+            end: end.clone(),
+        };
+        let root = self.root;
+        let (root, removed) = self.remove_at(root, &interval);
+        self.root = root;
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Iterates, in interval order, over the entries overlapping
+    /// `[start, end]`.
+    pub fn overlapping(&self, start: T, end: T) -> Overlapping<'_, T, V> {
+        let mut iter = Overlapping {
+            tree: self,
+            stack: Vec::new(),
+            start,
+            end,
+        };
+        iter.push_left_spine(self.root);
+        iter
+    }
+
+    /// Iterates over the entries whose interval contains `point`.
+    pub fn stabbing(&self, point: T) -> Overlapping<'_, T, V> {
+        self.overlapping(point.clone(), point)
+    }
+
+    /// Iterates over all entries in interval order.
+    pub fn iter(&self) -> MapIter<'_, Interval<T>, V, IntervalMeta<T>> {
+        self.nodes.iter(self.root)
+    }
+
+    fn insert_at(&mut self, tree: Option<usize>, interval: Interval<T>, value: V) -> usize {
+        let n = match tree {
+            Some(n) => n,
+            None => return self.nodes.insert_leaf(interval, value),
+        };
+        if interval < self.nodes[n].key {
+            let left = self.nodes[n].left;
+            self.nodes[n].left = Some(self.insert_at(left, interval, value));
+        } else {
+            let right = self.nodes[n].right;
+            self.nodes[n].right = Some(self.insert_at(right, interval, value));
+        }
+        self.nodes.rebalance(n)
+    }
+
+    fn remove_at(&mut self, tree: Option<usize>, interval: &Interval<T>) -> (Option<usize>, Option<V>) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (None, None),
+        };
+        let removed = match interval.cmp(&self.nodes[n].key) {
+            Ordering::Less => {
+                let left = self.nodes[n].left;
+                let (left, removed) = self.remove_at(left, interval);
+                self.nodes[n].left = left;
+                removed
+            },
+            Ordering::Greater => {
+                let right = self.nodes[n].right;
+                let (right, removed) = self.remove_at(right, interval);
+                self.nodes[n].right = right;
+                removed
+            },
+            Ordering::Equal => {
+                let replacement = self.nodes.unlink(n);
+                return (replacement, Some(self.nodes.remove(n).value));
+            }
+        };
+        (Some(self.nodes.rebalance(n)), removed)
+    }
+
+    /// Panics unless the intervals are ordered and every node's height,
+    /// balance and maximum end point are correct.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        assert_eq!(self.check_subtree(self.root, None, None).0, self.len);
+    }
+
+    #[cfg(test)]
+    fn check_subtree(&self, tree: Option<usize>, lower: Option<&Interval<T>>, upper: Option<&Interval<T>>)
+        -> (usize, usize, Option<T>) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (0, 0, None),
+        };
+        let node = &self.nodes[n];
+        assert!(lower.is_none_or(|lower| *lower <= node.key), "intervals out of order");
+        assert!(upper.is_none_or(|upper| node.key <= *upper), "intervals out of order");
+        let (left_count, left_height, left_max) = self.check_subtree(node.left, lower, Some(&node.key));
+        let (right_count, right_height, right_max) = self.check_subtree(node.right, Some(&node.key), upper);
+        assert!((left_height as isize - right_height as isize).abs() <= 1, "node out of balance");
+        let height = 1 + cmp::max(left_height, right_height);
+        let max_end = cmp::max(Some(node.key.end.clone()), cmp::max(left_max, right_max)).unwrap();
+        assert_eq!(node.meta.height, height, "stale height");
+        assert!(node.meta.max_end == max_end, "stale maximum end point");
+        (1 + left_count + right_count, height, Some(max_end))
+    }
+}
+
+impl<'a, T: Ord + Clone, V> IntoIterator for &'a IntervalTree<T, V> {
+    type Item = (&'a Interval<T>, &'a V);
+    type IntoIter = MapIter<'a, Interval<T>, V, IntervalMeta<T>>;
+
+    fn into_iter(self) -> MapIter<'a, Interval<T>, V, IntervalMeta<T>> {
+        self.iter()
+    }
+}
+
+/// In-order walk that skips every subtree whose maximum end point falls
+/// before the query and stops at the first interval starting after it.
+pub struct Overlapping<'a, T: 'a, V: 'a> {
+    tree: &'a IntervalTree<T, V>,
+    stack: Vec<usize>,
+    start: T,
+    end: T,
+}
+
+impl<'a, T: Ord, V> Overlapping<'a, T, V> {
+    fn push_left_spine(&mut self, mut tree: Option<usize>) {
+        while let Some(n) = tree {
+            let node = &self.tree.nodes[n];
+            if node.meta.max_end < self.start {
+                break;
+            }
+            self.stack.push(n);
+            tree = node.left;
+        }
+    }
+}
+
+impl<'a, T: Ord, V> Iterator for Overlapping<'a, T, V> {
+    type Item = (&'a Interval<T>, &'a V);
+
+    fn next(&mut self) -> Option<(&'a Interval<T>, &'a V)> {
+        let tree = self.tree;
+        while let Some(n) = self.stack.pop() {
+            let node = &tree.nodes[n];
+            if node.key.start > self.end {
+                // Everything left to visit starts even later.
+                self.stack.clear();
+                return None;
+            }
+            self.push_left_spine(node.right);
+            if node.key.end >= self.start {
+                return Some((&node.key, &node.value));
+            }
+        }
+        None
+    }
+}
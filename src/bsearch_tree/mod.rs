@@ -1,4 +1,25 @@
 use std::cmp::Ordering;
+use std::collections::Bound;
+use std::mem;
+use std::ops::RangeBounds;
+
+mod arena;
+mod avl;
+mod red_black;
+mod order_statistic;
+mod treap;
+mod splay;
+mod persistent;
+mod interval;
+
+pub use self::arena::{MapIter, Keys, Values};
+pub use self::avl::{AvlTreeMap, AvlEntry};
+pub use self::red_black::{RbTreeMap, RbEntry};
+pub use self::order_statistic::OrderStatisticTree;
+pub use self::treap::{Treap, TreapIter};
+pub use self::splay::{SplayTree, SplayIter};
+pub use self::persistent::{PersistentMap, PersistentIter};
+pub use self::interval::{Interval, IntervalTree, Overlapping};
 
 #[derive(Debug, PartialEq)]
 pub enum BSearchTree<T> {
@@ -69,7 +90,7 @@ impl<T: Ord> BSearchTree<T> {
         }
     }
 
-    pub fn search(&self, value: T) -> Option<&BSearchTree<T>> {
+    pub fn search(&self, value: &T) -> Option<&BSearchTree<T>> {
         match *self {
             BSearchTree::Empty => None,
             BSearchTree::Leaf(ref val) => {
@@ -88,11 +109,8 @@ impl<T: Ord> BSearchTree<T> {
         }
     }
 
-    pub fn contains(&self, value: T) -> bool {
-        match self.search(value) {
-            Some(_) => true,
-            None => false,
-        }
+    pub fn contains(&self, value: &T) -> bool {
+        self.search(value).is_some()
     }
 
     pub fn minimum(&self) -> Option<&BSearchTree<T>>{
@@ -110,8 +128,315 @@ impl<T: Ord> BSearchTree<T> {
             BSearchTree::Node(_, _, ref right) => right.maximum(),
         }
     }
+
+    /// Removes one occurrence of `value`, returning it if it was present.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let tree = mem::replace(self, BSearchTree::Empty);
+        let (tree, removed) = tree.remove_value(value);
+        *self = tree;
+        removed
+    }
+
+    // Builds a node, collapsing it to a leaf when both children are empty.
+    fn node(left: BSearchTree<T>, value: T, right: BSearchTree<T>) -> BSearchTree<T> {
+        match (left, right) {
+            (BSearchTree::Empty, BSearchTree::Empty) => BSearchTree::Leaf(value),
+            (left, right) => BSearchTree::Node(Box::new(left), value, Box::new(right)),
+        }
+    }
+
+    fn remove_value(self, value: &T) -> (BSearchTree<T>, Option<T>) {
+        match self {
+            BSearchTree::Empty => (BSearchTree::Empty, None),
+            BSearchTree::Leaf(val) => {
+                if *value == val {
+                    (BSearchTree::Empty, Some(val))
+                } else {
+                    (BSearchTree::Leaf(val), None)
+                }
+            },
+            BSearchTree::Node(left, val, right) => {
+                match value.cmp(&val) {
+                    Ordering::Less => {
+                        let (left, removed) = left.remove_value(value);
+                        (BSearchTree::node(left, val, *right), removed)
+                    },
+                    Ordering::Greater => {
+                        let (right, removed) = right.remove_value(value);
+                        (BSearchTree::node(*left, val, right), removed)
+                    },
+                    // Replace the value with its in-order successor, or
+                    // splice in the left subtree if there is none.
+                    Ordering::Equal => {
+                        match right.remove_min() {
+                            (right, Some(successor)) => {
+                                (BSearchTree::node(*left, successor, right), Some(val))
+                            },
+                            (_, None) => (*left, Some(val)),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn remove_min(self) -> (BSearchTree<T>, Option<T>) {
+        match self {
+            BSearchTree::Empty => (BSearchTree::Empty, None),
+            BSearchTree::Leaf(val) => (BSearchTree::Empty, Some(val)),
+            BSearchTree::Node(left, val, right) => {
+                match left.remove_min() {
+                    (left, Some(min)) => (BSearchTree::node(left, val, *right), Some(min)),
+                    (_, None) => (*right, Some(val)),
+                }
+            }
+        }
+    }
+
+    /// Largest value strictly less than `value`.
+    pub fn predecessor(&self, value: &T) -> Option<&T> {
+        self.lower(value, false)
+    }
+
+    /// Smallest value strictly greater than `value`.
+    pub fn successor(&self, value: &T) -> Option<&T> {
+        self.upper(value, false)
+    }
+
+    /// Largest value less than or equal to `value`.
+    pub fn floor(&self, value: &T) -> Option<&T> {
+        self.lower(value, true)
+    }
+
+    /// Smallest value greater than or equal to `value`.
+    pub fn ceiling(&self, value: &T) -> Option<&T> {
+        self.upper(value, true)
+    }
+
+    fn lower(&self, value: &T, inclusive: bool) -> Option<&T> {
+        let mut best = None;
+        let mut tree = self;
+        loop {
+            match *tree {
+                BSearchTree::Empty => return best,
+                BSearchTree::Leaf(ref val) => {
+                    if *val < *value || (inclusive && *val == *value) {
+                        return Some(val);
+                    }
+                    return best;
+                },
+                BSearchTree::Node(ref left, ref val, ref right) => {
+                    if *val < *value || (inclusive && *val == *value) {
+                        best = Some(val);
+                        tree = right;
+                    } else {
+                        tree = left;
+                    }
+                }
+            }
+        }
+    }
+
+    fn upper(&self, value: &T, inclusive: bool) -> Option<&T> {
+        let mut best = None;
+        let mut tree = self;
+        loop {
+            match *tree {
+                BSearchTree::Empty => return best,
+                BSearchTree::Leaf(ref val) => {
+                    if *val > *value || (inclusive && *val == *value) {
+                        return Some(val);
+                    }
+                    return best;
+                },
+                BSearchTree::Node(ref left, ref val, ref right) => {
+                    if *val > *value || (inclusive && *val == *value) {
+                        best = Some(val);
+                        tree = left;
+                    } else {
+                        tree = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// In-order (ascending) iteration.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut stack = Vec::new();
+        push_left_spine(&mut stack, self);
+        Iter { stack }
+    }
+
+    pub fn pre_order_iter(&self) -> PreOrderIter<'_, T> {
+        PreOrderIter { stack: vec![self] }
+    }
+
+    pub fn post_order_iter(&self) -> PostOrderIter<'_, T> {
+        PostOrderIter { stack: vec![(self, false)] }
+    }
+
+    /// Ascending iteration over the values that fall within `range`.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        let mut stack = Vec::new();
+        let mut tree = self;
+        loop {
+            match *tree {
+                BSearchTree::Empty => break,
+                BSearchTree::Leaf(ref val) => {
+                    if !below_start(&range, val) {
+                        stack.push(tree);
+                    }
+                    break;
+                },
+                BSearchTree::Node(ref left, ref val, ref right) => {
+                    if below_start(&range, val) {
+                        tree = right;
+                    } else {
+                        stack.push(tree);
+                        tree = left;
+                    }
+                }
+            }
+        }
+        Range { stack, range }
+    }
+}
+
+fn push_left_spine<'a, T>(stack: &mut Vec<&'a BSearchTree<T>>, mut tree: &'a BSearchTree<T>) {
+    loop {
+        match *tree {
+            BSearchTree::Empty => return,
+            BSearchTree::Leaf(_) => {
+                stack.push(tree);
+                return;
+            },
+            BSearchTree::Node(ref left, _, _) => {
+                stack.push(tree);
+                tree = left;
+            }
+        }
+    }
+}
+
+// Pops the next in-order node, queueing the left spine of its right subtree.
+fn next_in_order<'a, T>(stack: &mut Vec<&'a BSearchTree<T>>) -> Option<&'a T> {
+    match stack.pop() {
+        Some(BSearchTree::Leaf(val)) => Some(val),
+        Some(BSearchTree::Node(_, val, right)) => {
+            push_left_spine(stack, right);
+            Some(val)
+        },
+        _ => None,
+    }
+}
+
+fn below_start<T: Ord, R: RangeBounds<T>>(range: &R, value: &T) -> bool {
+    match range.start_bound() {
+        Bound::Included(start) => value < start,
+        Bound::Excluded(start) => value <= start,
+        Bound::Unbounded => false,
+    }
+}
+
+fn above_end<T: Ord, R: RangeBounds<T>>(range: &R, value: &T) -> bool {
+    match range.end_bound() {
+        Bound::Included(end) => value > end,
+        Bound::Excluded(end) => value >= end,
+        Bound::Unbounded => false,
+    }
+}
+
+pub struct Iter<'a, T: 'a> {
+    stack: Vec<&'a BSearchTree<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        next_in_order(&mut self.stack)
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a BSearchTree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct PreOrderIter<'a, T: 'a> {
+    stack: Vec<&'a BSearchTree<T>>,
+}
+
+impl<'a, T> Iterator for PreOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        while let Some(tree) = self.stack.pop() {
+            match *tree {
+                BSearchTree::Empty => (),
+                BSearchTree::Leaf(ref val) => return Some(val),
+                BSearchTree::Node(ref left, ref val, ref right) => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                    return Some(val);
+                }
+            }
+        }
+        None
+    }
 }
 
+// Each node is visited twice: first to queue its children, then (with the
+// flag set) to yield its own value after they are done.
+pub struct PostOrderIter<'a, T: 'a> {
+    stack: Vec<(&'a BSearchTree<T>, bool)>,
+}
+
+impl<'a, T> Iterator for PostOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        while let Some((tree, expanded)) = self.stack.pop() {
+            match *tree {
+                BSearchTree::Empty => (),
+                BSearchTree::Leaf(ref val) => return Some(val),
+                BSearchTree::Node(_, ref val, _) if expanded => return Some(val),
+                BSearchTree::Node(ref left, _, ref right) => {
+                    self.stack.push((tree, true));
+                    self.stack.push((right, false));
+                    self.stack.push((left, false));
+                }
+            }
+        }
+        None
+    }
+}
+
+pub struct Range<'a, T: 'a, R> {
+    stack: Vec<&'a BSearchTree<T>>,
+    range: R,
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Range<'a, T, R> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let value = next_in_order(&mut self.stack)?;
+        if above_end(&self.range, value) {
+            self.stack.clear();
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+
 #[cfg(test)]
 mod test;
 
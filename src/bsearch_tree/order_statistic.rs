@@ -0,0 +1,226 @@
+use std::cmp::{self, Ordering};
+
+use super::arena::{Arena, Keys};
+use super::avl::Augment;
+
+// Per-node AVL height plus the number of elements in the subtree.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeMeta {
+    height: usize,
+    size: usize,
+}
+
+impl<T> Augment<T, ()> for SizeMeta {
+    fn compute(_: &T, _: &(), left: Option<&SizeMeta>, right: Option<&SizeMeta>) -> SizeMeta {
+        let (left_height, left_size) = left.map_or((0, 0), |meta| (meta.height, meta.size));
+        let (right_height, right_size) = right.map_or((0, 0), |meta| (meta.height, meta.size));
+        SizeMeta {
+            height: 1 + cmp::max(left_height, right_height),
+            size: 1 + left_size + right_size,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// AVL tree over a multiset, augmented with subtree sizes so that `rank`
+/// and `select` run in O(log n) alongside insertions and removals. Equal
+/// elements are kept as separate nodes.
+pub struct OrderStatisticTree<T> {
+    nodes: Arena<T, (), SizeMeta>,
+    root: Option<usize>,
+}
+
+impl<T: Ord> OrderStatisticTree<T> {
+    pub fn new() -> OrderStatisticTree<T> {
+        OrderStatisticTree {
+            nodes: Arena::new(),
+            root: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size_of(self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn height(&self) -> usize {
+        self.nodes.height_of(self.root)
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+    }
+
+    pub fn insert(&mut self, value: T) {
+        let root = self.root;
+        self.root = Some(self.insert_at(root, value));
+    }
+
+    /// Removes one occurrence of `value`, returning it if it was present.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let root = self.root;
+        let (root, removed) = self.remove_at(root, value);
+        self.root = root;
+        removed
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.nodes.find(self.root, value).is_some()
+    }
+
+    /// Number of occurrences of `value`.
+    pub fn count(&self, value: &T) -> usize {
+        self.rank_upper(value) - self.rank(value)
+    }
+
+    /// Number of elements strictly less than `value`.
+    pub fn rank(&self, value: &T) -> usize {
+        let mut rank = 0;
+        let mut tree = self.root;
+        while let Some(n) = tree {
+            if self.nodes[n].key < *value {
+                rank += self.size_of(self.nodes[n].left) + 1;
+                tree = self.nodes[n].right;
+            } else {
+                tree = self.nodes[n].left;
+            }
+        }
+        rank
+    }
+
+    // Number of elements less than or equal to `value`.
+    fn rank_upper(&self, value: &T) -> usize {
+        let mut rank = 0;
+        let mut tree = self.root;
+        while let Some(n) = tree {
+            if self.nodes[n].key <= *value {
+                rank += self.size_of(self.nodes[n].left) + 1;
+                tree = self.nodes[n].right;
+            } else {
+                tree = self.nodes[n].left;
+            }
+        }
+        rank
+    }
+
+    /// The `k`-th smallest element, counting from zero.
+    pub fn select(&self, mut k: usize) -> Option<&T> {
+        let mut tree = self.root;
+        while let Some(n) = tree {
+            let left_size = self.size_of(self.nodes[n].left);
+            match k.cmp(&left_size) {
+                Ordering::Less => tree = self.nodes[n].left,
+                Ordering::Equal => return Some(&self.nodes[n].key),
+                Ordering::Greater => {
+                    k -= left_size + 1;
+                    tree = self.nodes[n].right;
+                }
+            }
+        }
+        None
+    }
+
+    /// Nearest-rank quantile for `q` in `[0, 1]`, e.g. `quantile(0.5)` is the
+    /// (lower) median.
+    pub fn quantile(&self, q: f64) -> Option<&T> {
+        assert!((0.0..=1.0).contains(&q), "quantile must be within [0, 1]");
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let k = (q * len as f64).ceil() as usize;
+        self.select(k.clamp(1, len) - 1)
+    }
+
+    /// Iterates over the elements in ascending order.
+    pub fn iter(&self) -> Keys<'_, T, (), SizeMeta> {
+        self.nodes.keys(self.root)
+    }
+
+    fn size_of(&self, tree: Option<usize>) -> usize {
+        tree.map_or(0, |n| self.nodes[n].meta.size)
+    }
+
+    fn insert_at(&mut self, tree: Option<usize>, value: T) -> usize {
+        let n = match tree {
+            Some(n) => n,
+            None => return self.nodes.insert_leaf(value, ()),
+        };
+        if value < self.nodes[n].key {
+            let left = self.nodes[n].left;
+            self.nodes[n].left = Some(self.insert_at(left, value));
+        } else {
+            let right = self.nodes[n].right;
+            self.nodes[n].right = Some(self.insert_at(right, value));
+        }
+        self.nodes.rebalance(n)
+    }
+
+    fn remove_at(&mut self, tree: Option<usize>, value: &T) -> (Option<usize>, Option<T>) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (None, None),
+        };
+        let removed = match value.cmp(&self.nodes[n].key) {
+            Ordering::Less => {
+                let left = self.nodes[n].left;
+                let (left, removed) = self.remove_at(left, value);
+                self.nodes[n].left = left;
+                removed
+            },
+            Ordering::Greater => {
+                let right = self.nodes[n].right;
+                let (right, removed) = self.remove_at(right, value);
+                self.nodes[n].right = right;
+                removed
+            },
+            Ordering::Equal => {
+                let replacement = self.nodes.unlink(n);
+                return (replacement, Some(self.nodes.remove(n).key));
+            }
+        };
+        (Some(self.nodes.rebalance(n)), removed)
+    }
+
+    /// Panics unless the elements are ordered and every node's height,
+    /// balance and subtree size are correct.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        self.check_subtree(self.root, None, None);
+    }
+
+    #[cfg(test)]
+    fn check_subtree(&self, tree: Option<usize>, lower: Option<&T>, upper: Option<&T>) -> (usize, usize) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (0, 0),
+        };
+        let node = &self.nodes[n];
+        assert!(lower.is_none_or(|lower| *lower <= node.key), "elements out of order");
+        assert!(upper.is_none_or(|upper| node.key <= *upper), "elements out of order");
+        let (left_size, left_height) = self.check_subtree(node.left, lower, Some(&node.key));
+        let (right_size, right_height) = self.check_subtree(node.right, Some(&node.key), upper);
+        assert!((left_height as isize - right_height as isize).abs() <= 1, "node out of balance");
+        let height = 1 + cmp::max(left_height, right_height);
+        let size = 1 + left_size + right_size;
+        assert_eq!(node.meta.height, height, "stale height");
+        assert_eq!(node.meta.size, size, "stale size");
+        (size, height)
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a OrderStatisticTree<T> {
+    type Item = &'a T;
+    type IntoIter = Keys<'a, T, (), SizeMeta>;
+
+    fn into_iter(self) -> Keys<'a, T, (), SizeMeta> {
+        self.iter()
+    }
+}
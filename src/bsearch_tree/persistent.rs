@@ -0,0 +1,251 @@
+use std::cmp::{self, Ordering};
+use std::rc::Rc;
+
+type Tree<K, V> = Option<Rc<PersistentNode<K, V>>>;
+
+// Nodes are immutable once built; updates copy the search path and share
+// every untouched subtree with the previous version.
+struct PersistentNode<K, V> {
+    key: K,
+    value: V,
+    height: usize,
+    left: Tree<K, V>,
+    right: Tree<K, V>,
+}
+
+fn height<K, V>(tree: &Tree<K, V>) -> usize {
+    tree.as_ref().map_or(0, |node| node.height)
+}
+
+fn node<K, V>(key: K, value: V, left: Tree<K, V>, right: Tree<K, V>) -> Rc<PersistentNode<K, V>> {
+    Rc::new(PersistentNode {
+        height: 1 + cmp::max(height(&left), height(&right)),
+        key,
+        value,
+        left,
+        right,
+    })
+}
+
+// Builds a node from parts whose heights differ by at most two, rotating
+// as needed to restore the AVL property.
+fn balance<K: Clone, V: Clone>(key: K, value: V, left: Tree<K, V>, right: Tree<K, V>) -> Rc<PersistentNode<K, V>> {
+    let (hl, hr) = (height(&left), height(&right));
+    if hl > hr + 1 {
+        let l = left.unwrap();
+        if height(&l.left) >= height(&l.right) {
+            let right = node(key, value, l.right.clone(), right);
+            node(l.key.clone(), l.value.clone(), l.left.clone(), Some(right))
+        } else {
+            let lr = l.right.as_ref().unwrap();
+            let left = node(l.key.clone(), l.value.clone(), l.left.clone(), lr.left.clone());
+            let right = node(key, value, lr.right.clone(), right);
+            node(lr.key.clone(), lr.value.clone(), Some(left), Some(right))
+        }
+    } else if hr > hl + 1 {
+        let r = right.unwrap();
+        if height(&r.right) >= height(&r.left) {
+            let left = node(key, value, left, r.left.clone());
+            node(r.key.clone(), r.value.clone(), Some(left), r.right.clone())
+        } else {
+            let rl = r.left.as_ref().unwrap();
+            let left = node(key, value, left, rl.left.clone());
+            let right = node(r.key.clone(), r.value.clone(), rl.right.clone(), r.right.clone());
+            node(rl.key.clone(), rl.value.clone(), Some(left), Some(right))
+        }
+    } else {
+        node(key, value, left, right)
+    }
+}
+
+// Returns the new tree and whether `key` was already present.
+fn insert<K: Ord + Clone, V: Clone>(tree: &Tree<K, V>, key: K, value: V) -> (Rc<PersistentNode<K, V>>, bool) {
+    let n = match *tree {
+        Some(ref n) => n,
+        None => return (node(key, value, None, None), false),
+    };
+    match key.cmp(&n.key) {
+        Ordering::Less => {
+            let (left, replaced) = insert(&n.left, key, value);
+            (balance(n.key.clone(), n.value.clone(), Some(left), n.right.clone()), replaced)
+        },
+        Ordering::Greater => {
+            let (right, replaced) = insert(&n.right, key, value);
+            (balance(n.key.clone(), n.value.clone(), n.left.clone(), Some(right)), replaced)
+        },
+        Ordering::Equal => (node(key, value, n.left.clone(), n.right.clone()), true),
+    }
+}
+
+// `None` when `key` is absent, so the caller can keep sharing the old tree.
+fn remove<K: Ord + Clone, V: Clone>(tree: &Tree<K, V>, key: &K) -> Option<Tree<K, V>> {
+    let n = tree.as_ref()?;
+    match key.cmp(&n.key) {
+        Ordering::Less => {
+            let left = remove(&n.left, key)?;
+            Some(Some(balance(n.key.clone(), n.value.clone(), left, n.right.clone())))
+        },
+        Ordering::Greater => {
+            let right = remove(&n.right, key)?;
+            Some(Some(balance(n.key.clone(), n.value.clone(), n.left.clone(), right)))
+        },
+        Ordering::Equal => {
+            match n.right {
+                None => Some(n.left.clone()),
+                Some(ref right) => {
+                    let (right, key, value) = remove_min(right);
+                    Some(Some(balance(key, value, n.left.clone(), right)))
+                }
+            }
+        }
+    }
+}
+
+fn remove_min<K: Clone, V: Clone>(n: &Rc<PersistentNode<K, V>>) -> (Tree<K, V>, K, V) {
+    match n.left {
+        None => (n.right.clone(), n.key.clone(), n.value.clone()),
+        Some(ref left) => {
+            let (left, key, value) = remove_min(left);
+            (Some(balance(n.key.clone(), n.value.clone(), left, n.right.clone())), key, value)
+        }
+    }
+}
+
+/// Immutable AVL map. `insert` and `remove` return a new version in
+/// O(log n) time and space while the receiver stays valid; versions share
+/// all unchanged nodes through `Rc`, so cloning a map is O(1).
+pub struct PersistentMap<K, V> {
+    root: Tree<K, V>,
+    len: usize,
+}
+
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> PersistentMap<K, V> {
+        PersistentMap {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    pub fn new() -> PersistentMap<K, V> {
+        PersistentMap {
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut tree = &self.root;
+        while let Some(ref n) = *tree {
+            tree = match key.cmp(&n.key) {
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+                Ordering::Equal => return Some(&n.value),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// A new version with `value` stored under `key`.
+    pub fn insert(&self, key: K, value: V) -> PersistentMap<K, V> {
+        let (root, replaced) = insert(&self.root, key, value);
+        PersistentMap {
+            root: Some(root),
+            len: if replaced { self.len } else { self.len + 1 },
+        }
+    }
+
+    /// A new version without `key`; shares the whole tree if it is absent.
+    pub fn remove(&self, key: &K) -> PersistentMap<K, V> {
+        match remove(&self.root, key) {
+            Some(root) => PersistentMap { root, len: self.len - 1 },
+            None => self.clone(),
+        }
+    }
+
+    /// Whether both versions are backed by the very same root node.
+    pub fn ptr_eq(&self, other: &PersistentMap<K, V>) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        let mut iter = PersistentIter { stack: Vec::new() };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+
+    /// Panics unless the keys are ordered and every node's height and
+    /// balance are correct.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        fn check<K: Ord, V>(tree: &Tree<K, V>, lower: Option<&K>, upper: Option<&K>) -> (usize, usize) {
+            let n = match *tree {
+                Some(ref n) => n,
+                None => return (0, 0),
+            };
+            assert!(lower.is_none_or(|lower| *lower < n.key), "keys out of order");
+            assert!(upper.is_none_or(|upper| n.key < *upper), "keys out of order");
+            let (left_count, left_height) = check(&n.left, lower, Some(&n.key));
+            let (right_count, right_height) = check(&n.right, Some(&n.key), upper);
+            assert!((left_height as isize - right_height as isize).abs() <= 1, "node out of balance");
+            assert_eq!(n.height, 1 + cmp::max(left_height, right_height), "stale height");
+            (1 + left_count + right_count, n.height)
+        }
+        assert_eq!(check(&self.root, None, None).0, self.len);
+    }
+}
+
+impl<'a, K: Ord + Clone, V: Clone> IntoIterator for &'a PersistentMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = PersistentIter<'a, K, V>;
+
+    fn into_iter(self) -> PersistentIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+pub struct PersistentIter<'a, K: 'a, V: 'a> {
+    stack: Vec<&'a PersistentNode<K, V>>,
+}
+
+impl<'a, K, V> PersistentIter<'a, K, V> {
+    fn push_left_spine(&mut self, mut tree: &'a Tree<K, V>) {
+        while let Some(ref n) = *tree {
+            self.stack.push(n);
+            tree = &n.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for PersistentIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let n = self.stack.pop()?;
+        self.push_left_spine(&n.right);
+        Some((&n.key, &n.value))
+    }
+}
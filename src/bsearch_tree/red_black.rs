@@ -0,0 +1,359 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use super::arena::{Arena, Node, MapIter, Keys, Values};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+impl Color {
+    fn flip(self) -> Color {
+        match self {
+            Color::Red => Color::Black,
+            Color::Black => Color::Red,
+        }
+    }
+}
+
+type RbNode<K, V> = Node<K, V, Color>;
+
+/// Ordered map backed by a left-leaning red-black tree (Sedgewick): red
+/// links only ever lean left, which keeps insertion and removal down to a
+/// handful of local rotations and color flips.
+pub struct RbTreeMap<K, V> {
+    nodes: Arena<K, V, Color>,
+    root: Option<usize>,
+    len: usize,
+}
+
+impl<K: Ord, V> RbTreeMap<K, V> {
+    pub fn new() -> RbTreeMap<K, V> {
+        RbTreeMap {
+            nodes: Arena::new(),
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        self.height_of(self.root)
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+        self.len = 0;
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.nodes.find(self.root, key).map(|n| &self.nodes[n].value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.nodes.find(self.root, key) {
+            Some(n) => Some(&mut self.nodes[n].value),
+            None => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.nodes.find(self.root, key).is_some()
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_root(key, value).1
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let root = self.root.unwrap();
+        if !self.is_red(self.nodes[root].left) && !self.is_red(self.nodes[root].right) {
+            self.nodes[root].meta = Color::Red;
+        }
+        let (root, removed) = self.remove_at(root, key);
+        self.root = root;
+        if let Some(root) = root {
+            self.nodes[root].meta = Color::Black;
+        }
+        Some(removed.value)
+    }
+
+    pub fn entry(&mut self, key: K) -> RbEntry<'_, K, V> {
+        let index = self.nodes.find(self.root, &key);
+        RbEntry {
+            map: self,
+            key,
+            index,
+        }
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> MapIter<'_, K, V, Color> {
+        self.nodes.iter(self.root)
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, Color> {
+        self.nodes.keys(self.root)
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, Color> {
+        self.nodes.values(self.root)
+    }
+
+    fn height_of(&self, tree: Option<usize>) -> usize {
+        tree.map_or(0, |n| {
+            1 + ::std::cmp::max(self.height_of(self.nodes[n].left),
+                                self.height_of(self.nodes[n].right))
+        })
+    }
+
+    fn is_red(&self, tree: Option<usize>) -> bool {
+        tree.is_some_and(|n| self.nodes[n].meta == Color::Red)
+    }
+
+    fn left_is_red(&self, tree: Option<usize>) -> bool {
+        tree.is_some_and(|n| self.is_red(self.nodes[n].left))
+    }
+
+    fn rotate_left(&mut self, h: usize) -> usize {
+        let x = self.nodes[h].right.expect("rotate_left without a right child");
+        self.nodes[h].right = self.nodes[x].left;
+        self.nodes[x].left = Some(h);
+        self.nodes[x].meta = self.nodes[h].meta;
+        self.nodes[h].meta = Color::Red;
+        x
+    }
+
+    fn rotate_right(&mut self, h: usize) -> usize {
+        let x = self.nodes[h].left.expect("rotate_right without a left child");
+        self.nodes[h].left = self.nodes[x].right;
+        self.nodes[x].right = Some(h);
+        self.nodes[x].meta = self.nodes[h].meta;
+        self.nodes[h].meta = Color::Red;
+        x
+    }
+
+    fn flip_colors(&mut self, h: usize) {
+        self.nodes[h].meta = self.nodes[h].meta.flip();
+        for child in [self.nodes[h].left, self.nodes[h].right].iter() {
+            if let Some(c) = *child {
+                self.nodes[c].meta = self.nodes[c].meta.flip();
+            }
+        }
+    }
+
+    // Restores the left-leaning invariants on the way back up.
+    fn fix_up(&mut self, mut h: usize) -> usize {
+        if self.is_red(self.nodes[h].right) && !self.is_red(self.nodes[h].left) {
+            h = self.rotate_left(h);
+        }
+        if self.is_red(self.nodes[h].left) && self.left_is_red(self.nodes[h].left) {
+            h = self.rotate_right(h);
+        }
+        if self.is_red(self.nodes[h].left) && self.is_red(self.nodes[h].right) {
+            self.flip_colors(h);
+        }
+        h
+    }
+
+    // Assuming `h` is red and both its children are black, makes the left
+    // child or one of its children red.
+    fn move_red_left(&mut self, mut h: usize) -> usize {
+        self.flip_colors(h);
+        let right = self.nodes[h].right;
+        if self.left_is_red(right) {
+            let right = self.rotate_right(right.unwrap());
+            self.nodes[h].right = Some(right);
+            h = self.rotate_left(h);
+            self.flip_colors(h);
+        }
+        h
+    }
+
+    fn move_red_right(&mut self, mut h: usize) -> usize {
+        self.flip_colors(h);
+        if self.left_is_red(self.nodes[h].left) {
+            h = self.rotate_right(h);
+            self.flip_colors(h);
+        }
+        h
+    }
+
+    fn insert_root(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        let root = self.root;
+        let (root, idx, old) = self.insert_at(root, key, value);
+        self.nodes[root].meta = Color::Black;
+        self.root = Some(root);
+        (idx, old)
+    }
+
+    // Returns the new subtree root, the index of the node holding `key` and
+    // the replaced value, if any.
+    fn insert_at(&mut self, tree: Option<usize>, key: K, value: V) -> (usize, usize, Option<V>) {
+        let h = match tree {
+            Some(h) => h,
+            None => {
+                let h = self.nodes.insert(Node::new(key, value, Color::Red));
+                self.len += 1;
+                return (h, h, None);
+            }
+        };
+        let (idx, old) = match key.cmp(&self.nodes[h].key) {
+            Ordering::Less => {
+                let left = self.nodes[h].left;
+                let (left, idx, old) = self.insert_at(left, key, value);
+                self.nodes[h].left = Some(left);
+                (idx, old)
+            },
+            Ordering::Greater => {
+                let right = self.nodes[h].right;
+                let (right, idx, old) = self.insert_at(right, key, value);
+                self.nodes[h].right = Some(right);
+                (idx, old)
+            },
+            Ordering::Equal => (h, Some(mem::replace(&mut self.nodes[h].value, value))),
+        };
+        (self.fix_up(h), idx, old)
+    }
+
+    // `key` must be present in the subtree rooted at `h`.
+    fn remove_at(&mut self, mut h: usize, key: &K) -> (Option<usize>, RbNode<K, V>) {
+        let removed;
+        if *key < self.nodes[h].key {
+            if !self.is_red(self.nodes[h].left) && !self.left_is_red(self.nodes[h].left) {
+                h = self.move_red_left(h);
+            }
+            let (left, node) = self.remove_at(self.nodes[h].left.unwrap(), key);
+            self.nodes[h].left = left;
+            removed = node;
+        } else {
+            if self.is_red(self.nodes[h].left) {
+                h = self.rotate_right(h);
+            }
+            if *key == self.nodes[h].key && self.nodes[h].right.is_none() {
+                self.len -= 1;
+                return (None, self.nodes.remove(h));
+            }
+            if !self.is_red(self.nodes[h].right) && !self.left_is_red(self.nodes[h].right) {
+                h = self.move_red_right(h);
+            }
+            if *key == self.nodes[h].key {
+                // Splice the in-order successor into `h`'s position.
+                let (right, min) = self.detach_min(self.nodes[h].right.unwrap());
+                self.nodes[min].left = self.nodes[h].left;
+                self.nodes[min].right = right;
+                self.nodes[min].meta = self.nodes[h].meta;
+                self.len -= 1;
+                removed = self.nodes.remove(h);
+                h = min;
+            } else {
+                let (right, node) = self.remove_at(self.nodes[h].right.unwrap(), key);
+                self.nodes[h].right = right;
+                removed = node;
+            }
+        }
+        (Some(self.fix_up(h)), removed)
+    }
+
+    // Unlinks the minimum node of the subtree rooted at `h`, returning the
+    // new subtree root and the detached node.
+    fn detach_min(&mut self, mut h: usize) -> (Option<usize>, usize) {
+        if self.nodes[h].left.is_none() {
+            return (None, h);
+        }
+        if !self.is_red(self.nodes[h].left) && !self.left_is_red(self.nodes[h].left) {
+            h = self.move_red_left(h);
+        }
+        let (left, min) = self.detach_min(self.nodes[h].left.unwrap());
+        self.nodes[h].left = left;
+        (Some(self.fix_up(h)), min)
+    }
+
+    /// Panics unless the keys are ordered, the root is black, no red node
+    /// has a red child, red links lean left and every root-to-leaf path has
+    /// the same number of black nodes.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        assert!(!self.is_red(self.root), "red root");
+        let (count, _) = self.check_subtree(self.root, None, None);
+        assert_eq!(count, self.len);
+    }
+
+    #[cfg(test)]
+    fn check_subtree(&self, tree: Option<usize>, lower: Option<&K>, upper: Option<&K>) -> (usize, usize) {
+        let n = match tree {
+            Some(n) => n,
+            None => return (0, 1),
+        };
+        let node = &self.nodes[n];
+        assert!(lower.is_none_or(|lower| *lower < node.key), "keys out of order");
+        assert!(upper.is_none_or(|upper| node.key < *upper), "keys out of order");
+        assert!(!self.is_red(node.right), "right-leaning red link");
+        if node.meta == Color::Red {
+            assert!(!self.is_red(node.left), "red node with a red child");
+        }
+        let (left_count, left_black) = self.check_subtree(node.left, lower, Some(&node.key));
+        let (right_count, right_black) = self.check_subtree(node.right, Some(&node.key), upper);
+        assert_eq!(left_black, right_black, "unequal black height");
+        let black = left_black + if node.meta == Color::Black { 1 } else { 0 };
+        (1 + left_count + right_count, black)
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a RbTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = MapIter<'a, K, V, Color>;
+
+    fn into_iter(self) -> MapIter<'a, K, V, Color> {
+        self.iter()
+    }
+}
+
+/// A view into a single key of an `RbTreeMap`, which may or may not be
+/// present.
+pub struct RbEntry<'a, K: 'a, V: 'a> {
+    map: &'a mut RbTreeMap<K, V>,
+    key: K,
+    index: Option<usize>,
+}
+
+impl<'a, K: Ord, V> RbEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        let map = self.map;
+        let idx = match self.index {
+            Some(idx) => idx,
+            None => map.insert_root(self.key, default()).0,
+        };
+        &mut map.nodes[idx].value
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> RbEntry<'a, K, V> {
+        if let Some(idx) = self.index {
+            f(&mut self.map.nodes[idx].value);
+        }
+        self
+    }
+}
@@ -0,0 +1,234 @@
+use std::cmp::{self, Ordering};
+use std::mem;
+
+type Link<K, V> = Option<Box<SplayNode<K, V>>>;
+
+struct SplayNode<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+fn rotate_right<K, V>(mut h: Box<SplayNode<K, V>>) -> Box<SplayNode<K, V>> {
+    let mut x = h.left.take().expect("rotate_right without a left child");
+    h.left = x.right.take();
+    x.right = Some(h);
+    x
+}
+
+fn rotate_left<K, V>(mut h: Box<SplayNode<K, V>>) -> Box<SplayNode<K, V>> {
+    let mut x = h.right.take().expect("rotate_left without a right child");
+    h.right = x.left.take();
+    x.left = Some(h);
+    x
+}
+
+// Brings the node holding `key`, or the last node on its search path, to
+// the root. Top-down: nodes passed on the way down are split off into a
+// tree of smaller and a tree of larger keys, reassembled under the new
+// root at the end, so the depth of the tree never reaches the call stack.
+fn splay<K: Ord, V>(link: Link<K, V>, key: &K) -> Link<K, V> {
+    let mut root = link?;
+    // Split off in search order; each node hangs off the previous one on
+    // the right (smaller) or on the left (larger).
+    let mut smaller = Vec::new();
+    let mut larger = Vec::new();
+    loop {
+        match key.cmp(&root.key) {
+            Ordering::Less => {
+                if root.left.as_ref().is_some_and(|left| *key < left.key) {
+                    root = rotate_right(root);
+                }
+                match root.left.take() {
+                    Some(left) => larger.push(mem::replace(&mut root, left)),
+                    None => break,
+                }
+            },
+            Ordering::Greater => {
+                if root.right.as_ref().is_some_and(|right| *key > right.key) {
+                    root = rotate_left(root);
+                }
+                match root.right.take() {
+                    Some(right) => smaller.push(mem::replace(&mut root, right)),
+                    None => break,
+                }
+            },
+            Ordering::Equal => break,
+        }
+    }
+    root.left = smaller.into_iter().rev().fold(root.left.take(), |acc, mut node| {
+        node.right = acc;
+        Some(node)
+    });
+    root.right = larger.into_iter().rev().fold(root.right.take(), |acc, mut node| {
+        node.left = acc;
+        Some(node)
+    });
+    Some(root)
+}
+
+/// Self-adjusting ordered map: every access splays the touched node to the
+/// root, so recently and frequently used keys stay cheap to reach.
+/// Operations are O(log n) amortized.
+pub struct SplayTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K: Ord, V> SplayTree<K, V> {
+    pub fn new() -> SplayTree<K, V> {
+        SplayTree {
+            root: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<(&SplayNode<K, V>, usize)> = self.root.iter().map(|node| (&**node, 1)).collect();
+        while let Some((node, depth)) = stack.pop() {
+            height = cmp::max(height, depth);
+            stack.extend(node.left.iter().chain(node.right.iter()).map(|child| (&**child, depth + 1)));
+        }
+        height
+    }
+
+    /// Key currently at the root, i.e. the most recently accessed one.
+    pub fn root_key(&self) -> Option<&K> {
+        self.root.as_ref().map(|node| &node.key)
+    }
+
+    fn splay(&mut self, key: &K) -> bool {
+        self.root = splay(self.root.take(), key);
+        self.root.as_ref().is_some_and(|node| node.key == *key)
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.splay(key) {
+            self.root.as_ref().map(|node| &node.value)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if self.splay(key) {
+            self.root.as_mut().map(|node| &mut node.value)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.splay(key)
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present. The key ends up at the root either way.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut root = match splay(self.root.take(), &key) {
+            Some(root) => root,
+            None => {
+                self.root = Some(Box::new(SplayNode { key, value, left: None, right: None }));
+                self.len += 1;
+                return None;
+            }
+        };
+        let mut node = Box::new(SplayNode { key, value, left: None, right: None });
+        match node.key.cmp(&root.key) {
+            Ordering::Equal => {
+                let old = mem::replace(&mut root.value, node.value);
+                self.root = Some(root);
+                return Some(old);
+            },
+            Ordering::Less => {
+                node.left = root.left.take();
+                node.right = Some(root);
+            },
+            Ordering::Greater => {
+                node.right = root.right.take();
+                node.left = Some(root);
+            }
+        }
+        self.root = Some(node);
+        self.len += 1;
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.splay(key) {
+            return None;
+        }
+        let mut root = self.root.take().unwrap();
+        // Everything left of the root is smaller than `key`, so splaying for
+        // it there lifts the maximum, which has no right child.
+        self.root = match splay(root.left.take(), key) {
+            Some(mut max) => {
+                max.right = root.right.take();
+                Some(max)
+            },
+            None => root.right.take(),
+        };
+        self.len -= 1;
+        Some(root.value)
+    }
+
+    /// Iterates over the entries in ascending key order without splaying.
+    pub fn iter(&self) -> SplayIter<'_, K, V> {
+        let mut iter = SplayIter { stack: Vec::new() };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+}
+
+// A degenerate tree is as deep as it is long; free it without recursing.
+impl<K, V> Drop for SplayTree<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<SplayNode<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SplayTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = SplayIter<'a, K, V>;
+
+    fn into_iter(self) -> SplayIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+pub struct SplayIter<'a, K: 'a, V: 'a> {
+    stack: Vec<&'a SplayNode<K, V>>,
+}
+
+impl<'a, K, V> SplayIter<'a, K, V> {
+    fn push_left_spine(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(ref node) = *link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for SplayIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(&node.right);
+        Some((&node.key, &node.value))
+    }
+}
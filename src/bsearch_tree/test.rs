@@ -1,4 +1,6 @@
-use super::BSearchTree;
+use rng::XorShift64;
+
+use super::*;
 
 #[test]
 fn test_empty() {
@@ -37,13 +39,13 @@ fn test_insert() {
     assert_eq!(tree.minimum(), Some(&BSearchTree::Leaf(4)));
     assert_eq!(tree.maximum(), Some(&BSearchTree::Leaf(14)));
 
-    let node1 = tree.search(11).unwrap();
+    let node1 = tree.search(&11).unwrap();
     assert_eq!(node1.value(), Some(&11));
 
-    let node2 = tree.search(12);
+    let node2 = tree.search(&12);
     assert_eq!(node2, None);
-    assert!(tree.contains(13));
-    assert!(!tree.contains(15));
+    assert!(tree.contains(&13));
+    assert!(!tree.contains(&15));
 }
 
 struct Writer<T> {
@@ -91,5 +93,526 @@ fn test_in_order() {
     assert_eq!(result.flush(), vec![4, 5, 6, 8, 9, 11, 13, 14]);
 }
 
+fn sample_tree() -> BSearchTree<usize> {
+    let mut tree = BSearchTree::Empty;
+    for v in &[8, 5, 4, 6, 11, 13, 9, 14] {
+        tree = tree.insert(*v);
+    }
+    tree
+}
+
+#[test]
+fn test_iterators() {
+    let tree = sample_tree();
+
+    assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![4, 5, 6, 8, 9, 11, 13, 14]);
+    assert_eq!(tree.pre_order_iter().cloned().collect::<Vec<_>>(), vec![8, 5, 4, 6, 11, 9, 13, 14]);
+    assert_eq!(tree.post_order_iter().cloned().collect::<Vec<_>>(), vec![4, 6, 5, 9, 14, 13, 11, 8]);
+
+    let mut sum = 0;
+    for v in &tree {
+        sum += *v;
+    }
+    assert_eq!(sum, 70);
+
+    let empty: BSearchTree<usize> = BSearchTree::Empty;
+    assert_eq!(empty.iter().next(), None);
+    assert_eq!(empty.pre_order_iter().next(), None);
+    assert_eq!(empty.post_order_iter().next(), None);
+}
+
+#[test]
+fn test_range() {
+    let tree = sample_tree();
+
+    assert_eq!(tree.range(5..11).cloned().collect::<Vec<_>>(), vec![5, 6, 8, 9]);
+    assert_eq!(tree.range(5..=11).cloned().collect::<Vec<_>>(), vec![5, 6, 8, 9, 11]);
+    assert_eq!(tree.range(7..).cloned().collect::<Vec<_>>(), vec![8, 9, 11, 13, 14]);
+    assert_eq!(tree.range(..6).cloned().collect::<Vec<_>>(), vec![4, 5]);
+    assert_eq!(tree.range(..).count(), 8);
+    assert_eq!(tree.range(15..).next(), None);
+    assert_eq!(tree.range(10..11).next(), None);
+}
+
+#[test]
+fn test_neighbours() {
+    let tree = sample_tree();
+
+    assert_eq!(tree.predecessor(&8), Some(&6));
+    assert_eq!(tree.predecessor(&4), None);
+    assert_eq!(tree.predecessor(&10), Some(&9));
+    assert_eq!(tree.successor(&8), Some(&9));
+    assert_eq!(tree.successor(&14), None);
+    assert_eq!(tree.successor(&0), Some(&4));
 
+    assert_eq!(tree.floor(&8), Some(&8));
+    assert_eq!(tree.floor(&7), Some(&6));
+    assert_eq!(tree.floor(&3), None);
+    assert_eq!(tree.ceiling(&8), Some(&8));
+    assert_eq!(tree.ceiling(&12), Some(&13));
+    assert_eq!(tree.ceiling(&15), None);
+}
+
+#[test]
+fn test_remove() {
+    let mut tree = sample_tree();
+
+    // Leaf, node with one child, node with two children, then the root.
+    assert_eq!(tree.remove(&4), Some(4));
+    assert_eq!(tree.remove(&13), Some(13));
+    assert_eq!(tree.remove(&11), Some(11));
+    assert_eq!(tree.remove(&8), Some(8));
+    assert_eq!(tree.remove(&8), None);
+    assert_eq!(tree.remove(&100), None);
+
+    assert_eq!(tree.count(), 4);
+    assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![5, 6, 9, 14]);
+    assert!(!tree.contains(&8));
+    assert!(tree.contains(&9));
+
+    for v in &[5, 6, 9, 14] {
+        assert_eq!(tree.remove(v), Some(*v));
+    }
+    assert_eq!(tree, BSearchTree::Empty);
+}
+
+#[test]
+fn test_remove_duplicates() {
+    let mut tree = BSearchTree::Empty;
+    for v in &[3, 1, 3, 2, 3] {
+        tree = tree.insert(*v);
+    }
+    assert_eq!(tree.remove(&3), Some(3));
+    assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![1, 2, 3, 3]);
+    assert_eq!(tree.remove(&3), Some(3));
+    assert_eq!(tree.remove(&3), Some(3));
+    assert_eq!(tree.remove(&3), None);
+    assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![1, 2]);
+}
+
+macro_rules! test_balanced_map {
+    ($map_type: ident) => {
+        use std::collections::BTreeMap;
+
+        let mut map = $map_type::new();
+        assert!(map.is_empty());
+        assert_eq!(map.height(), 0);
+        assert_eq!(map.remove(&1), None);
+
+        // Sorted input must not degrade into a list.
+        for i in 0..1024 {
+            assert_eq!(map.insert(i, i * 10), None);
+        }
+        map.assert_invariants();
+        assert_eq!(map.len(), 1024);
+        assert!(map.height() <= 20);
+        assert_eq!(map.insert(7, 0), Some(70));
+        assert_eq!(map.get(&7), Some(&0));
+        *map.get_mut(&8).unwrap() += 1;
+        assert_eq!(map.get(&8), Some(&81));
+        assert_eq!(map.get(&5000), None);
 
+        // Random inserts and removals checked against BTreeMap.
+        let mut expected = BTreeMap::new();
+        map.clear();
+        let mut rng = XorShift64::new(42);
+        for round in 0..4000 {
+            let key = rng.next_below(300);
+            if rng.next_below(3) == 0 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, round), expected.insert(key, round));
+            }
+            if round % 97 == 0 {
+                map.assert_invariants();
+            }
+        }
+        map.assert_invariants();
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.keys().eq(expected.keys()));
+        assert!(map.values().eq(expected.values()));
+        assert_eq!((&map).into_iter().count(), expected.len());
+
+        let keys: Vec<u64> = expected.keys().cloned().collect();
+        for key in keys {
+            assert_eq!(map.remove(&key), expected.remove(&key));
+        }
+        map.assert_invariants();
+        assert!(map.is_empty());
+        assert_eq!(map.iter().next(), None);
+    }
+}
+
+#[test]
+fn test_avl_tree_map() {
+    test_balanced_map!(AvlTreeMap);
+}
+
+#[test]
+fn test_rb_tree_map() {
+    test_balanced_map!(RbTreeMap);
+}
+
+macro_rules! test_map_entry {
+    ($map_type: ident) => {
+        let mut map = $map_type::new();
+        for word in "the quick brown fox jumps over the lazy dog the end".split(' ') {
+            *map.entry(word).or_insert(0) += 1;
+        }
+        map.assert_invariants();
+        assert_eq!(map.get(&"the"), Some(&3));
+        assert_eq!(map.get(&"fox"), Some(&1));
+        assert_eq!(map.len(), 9);
+
+        map.entry("fox").and_modify(|v| *v += 10).or_insert(0);
+        map.entry("cat").and_modify(|v| *v += 10).or_insert(5);
+        assert_eq!(map.get(&"fox"), Some(&11));
+        assert_eq!(map.get(&"cat"), Some(&5));
+        assert_eq!(map.entry("owl").key(), &"owl");
+        assert_eq!(*map.entry("owl").or_insert_with(|| 2), 2);
+        map.assert_invariants();
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(),
+                   vec!["brown", "cat", "dog", "end", "fox", "jumps", "lazy", "over", "owl", "quick", "the"]);
+    }
+}
+
+#[test]
+fn test_avl_entry() {
+    test_map_entry!(AvlTreeMap);
+}
+
+#[test]
+fn test_rb_entry() {
+    test_map_entry!(RbTreeMap);
+}
+
+#[test]
+fn test_order_statistic() {
+    let mut tree = OrderStatisticTree::new();
+    assert_eq!(tree.select(0), None);
+    assert_eq!(tree.rank(&5), 0);
+    assert_eq!(tree.quantile(0.5), None);
+
+    for v in &[50, 20, 80, 20, 70, 10, 90, 20] {
+        tree.insert(*v);
+    }
+    tree.assert_invariants();
+    assert_eq!(tree.len(), 8);
+    assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![10, 20, 20, 20, 50, 70, 80, 90]);
+    assert_eq!(tree.select(0), Some(&10));
+    assert_eq!(tree.select(3), Some(&20));
+    assert_eq!(tree.select(7), Some(&90));
+    assert_eq!(tree.select(8), None);
+    assert_eq!(tree.rank(&10), 0);
+    assert_eq!(tree.rank(&20), 1);
+    assert_eq!(tree.rank(&21), 4);
+    assert_eq!(tree.rank(&100), 8);
+    assert_eq!(tree.count(&20), 3);
+    assert_eq!(tree.count(&30), 0);
+    assert_eq!(tree.quantile(0.0), Some(&10));
+    assert_eq!(tree.quantile(0.5), Some(&20));
+    assert_eq!(tree.quantile(0.9), Some(&90));
+    assert_eq!(tree.quantile(1.0), Some(&90));
+
+    assert_eq!(tree.remove(&20), Some(20));
+    assert_eq!(tree.remove(&30), None);
+    tree.assert_invariants();
+    assert_eq!(tree.count(&20), 2);
+    assert_eq!(tree.select(4), Some(&70));
+}
+
+#[test]
+fn test_order_statistic_random() {
+    let mut tree = OrderStatisticTree::new();
+    let mut expected: Vec<u64> = Vec::new();
+    let mut rng = XorShift64::new(7);
+    for round in 0..3000 {
+        let value = rng.next_below(100);
+        if rng.next_below(3) == 1 {
+            let pos = expected.binary_search(&value).ok();
+            assert_eq!(tree.remove(&value), pos.map(|pos| expected.remove(pos)));
+        } else {
+            tree.insert(value);
+            let pos = expected.binary_search(&value).unwrap_or_else(|pos| pos);
+            expected.insert(pos, value);
+        }
+        if round % 50 == 49 {
+            tree.assert_invariants();
+            for (k, v) in expected.iter().enumerate() {
+                assert_eq!(tree.select(k), Some(v));
+            }
+            for v in 0..101 {
+                assert_eq!(tree.rank(&v), expected.iter().filter(|x| **x < v).count());
+            }
+        }
+    }
+    assert_eq!(tree.len(), expected.len());
+    assert!(tree.height() <= 2 * 12);
+}
+
+#[test]
+fn test_treap_map() {
+    use std::collections::BTreeMap;
+
+    let mut treap = Treap::with_seed(11);
+    let mut expected = BTreeMap::new();
+    let mut rng = XorShift64::new(3);
+    for round in 0..4000 {
+        let key = rng.next_below(300);
+        if rng.next_below(3) == 1 {
+            assert_eq!(treap.remove(&key), expected.remove(&key));
+        } else {
+            assert_eq!(treap.insert(key, round), expected.insert(key, round));
+        }
+    }
+    treap.assert_invariants();
+    assert_eq!(treap.len(), expected.len());
+    assert!(treap.iter().eq(expected.iter()));
+    assert_eq!(treap.get(&1000), None);
+    assert!(treap.height() <= 40);
+
+    for (i, (k, v)) in expected.iter().enumerate() {
+        assert_eq!(treap.get_index(i), Some((k, v)));
+    }
+    assert_eq!(treap.get_index(expected.len()), None);
+}
+
+#[test]
+fn test_treap_split_merge() {
+    let mut treap = Treap::with_seed(1);
+    for i in 0..100 {
+        treap.insert(i, i * i);
+    }
+
+    // Cut out [30, 60) and splice the outer parts back together.
+    let mut upper = treap.split_off(&30);
+    let mut tail = upper.split_off(&60);
+    treap.assert_invariants();
+    upper.assert_invariants();
+    tail.assert_invariants();
+    assert_eq!(treap.len(), 30);
+    assert_eq!(upper.iter().map(|(k, _)| *k).collect::<Vec<_>>(), (30..60).collect::<Vec<_>>());
+    assert_eq!(tail.len(), 40);
+
+    treap.append(&mut tail);
+    treap.assert_invariants();
+    assert!(tail.is_empty());
+    assert_eq!(treap.len(), 70);
+    assert_eq!(treap.get(&75), Some(&5625));
+    assert_eq!(treap.get(&45), None);
+
+    let rest = treap.split_at(10);
+    assert_eq!(treap.iter().map(|(k, _)| *k).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    assert_eq!(rest.get_index(0), Some((&10, &100)));
+    assert_eq!(rest.len(), 60);
+}
+
+#[test]
+#[should_panic]
+fn test_treap_append_out_of_order() {
+    let mut a = Treap::new();
+    let mut b = Treap::new();
+    a.insert(5, ());
+    b.insert(3, ());
+    a.append(&mut b);
+}
+
+#[test]
+fn test_splay_tree() {
+    use std::collections::BTreeMap;
+
+    let mut tree = SplayTree::new();
+    assert_eq!(tree.get(&1), None);
+    assert_eq!(tree.remove(&1), None);
+
+    let mut expected = BTreeMap::new();
+    let mut rng = XorShift64::new(17);
+    for round in 0..3000 {
+        let key = rng.next_below(200);
+        match rng.next_below(4) {
+            0 => assert_eq!(tree.remove(&key), expected.remove(&key)),
+            1 => assert_eq!(tree.get(&key), expected.get(&key)),
+            _ => assert_eq!(tree.insert(key, round), expected.insert(key, round)),
+        }
+    }
+    assert_eq!(tree.len(), expected.len());
+    assert!(tree.iter().eq(expected.iter()));
+
+    // Accessed keys move to the root.
+    let key = *expected.keys().nth(expected.len() / 3).unwrap();
+    *tree.get_mut(&key).unwrap() = 0;
+    assert_eq!(tree.root_key(), Some(&key));
+    assert!(tree.contains_key(&key));
+    assert_eq!(tree.get(&key), Some(&0));
+}
+
+#[test]
+fn test_splay_sequential_access() {
+    let mut tree = SplayTree::new();
+    for i in 0..1000 {
+        tree.insert(i, ());
+    }
+    // Ascending inserts leave a path; one pass of lookups roughly halves
+    // its depth.
+    assert_eq!(tree.height(), 1000);
+    assert!(tree.contains_key(&0));
+    assert!(tree.height() < 600);
+    for i in 0..1000 {
+        assert!(tree.contains_key(&i));
+    }
+    assert_eq!(tree.root_key(), Some(&999));
+}
+
+#[test]
+fn test_splay_deep_path() {
+    // Sorted inserts build a path as long as the tree.
+    let mut tree = SplayTree::new();
+    for i in 0..100_000 {
+        tree.insert(i, i);
+    }
+    assert_eq!(tree.height(), 100_000);
+    assert_eq!(tree.get(&0), Some(&0));
+    assert_eq!(tree.root_key(), Some(&0));
+    assert!(tree.height() < 60_000);
+    assert_eq!(tree.len(), 100_000);
+    drop(tree);
+}
+
+#[test]
+fn test_persistent_map() {
+    let empty = PersistentMap::new();
+    let v1 = empty.insert(1, "one").insert(2, "two").insert(3, "three");
+    let v2 = v1.insert(2, "TWO").insert(4, "four");
+    let v3 = v2.remove(&1);
+    let v4 = v3.remove(&100);
+
+    // Every earlier version is still intact.
+    assert!(empty.is_empty());
+    assert_eq!(v1.len(), 3);
+    assert_eq!(v1.get(&2), Some(&"two"));
+    assert_eq!(v1.get(&4), None);
+    assert_eq!(v2.len(), 4);
+    assert_eq!(v2.get(&2), Some(&"TWO"));
+    assert_eq!(v2.get(&1), Some(&"one"));
+    assert_eq!(v3.len(), 3);
+    assert!(!v3.contains_key(&1));
+    assert!(v4.ptr_eq(&v3));
+    assert!(!v3.ptr_eq(&v2));
+    assert_eq!(v3.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+               vec![(2, "TWO"), (3, "three"), (4, "four")]);
+}
+
+#[test]
+fn test_persistent_map_versions() {
+    let mut versions = vec![PersistentMap::new()];
+    let mut rng = XorShift64::new(23);
+    for round in 0..1500 {
+        let key = rng.next_below(150);
+        let next = if rng.next_below(3) == 1 {
+            versions.last().unwrap().remove(&key)
+        } else {
+            versions.last().unwrap().insert(key, round)
+        };
+        versions.push(next);
+    }
+
+    // Replay the same operations on a mutable map and compare each version.
+    let mut expected = ::std::collections::BTreeMap::new();
+    let mut rng = XorShift64::new(23);
+    for (round, version) in versions.iter().enumerate().skip(1) {
+        let key = rng.next_below(150);
+        if rng.next_below(3) == 1 {
+            expected.remove(&key);
+        } else {
+            expected.insert(key, round - 1);
+        }
+        if round % 100 == 1 {
+            version.assert_invariants();
+            assert!(version.iter().eq(expected.iter()));
+        }
+    }
+    let last = versions.last().unwrap();
+    last.assert_invariants();
+    assert!(last.iter().eq(expected.iter()));
+    assert!(last.height() <= 12);
+}
+
+#[test]
+fn test_interval_tree() {
+    let mut tree = IntervalTree::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.stabbing(5).next(), None);
+
+    tree.insert(15, 20, "a");
+    tree.insert(10, 30, "b");
+    tree.insert(17, 19, "c");
+    tree.insert(5, 20, "d");
+    tree.insert(12, 15, "e");
+    tree.insert(30, 40, "f");
+    tree.assert_invariants();
+    assert_eq!(tree.len(), 6);
+
+    let found: Vec<_> = tree.overlapping(6, 7).map(|(_, v)| *v).collect();
+    assert_eq!(found, vec!["d"]);
+    let found: Vec<_> = tree.overlapping(21, 23).map(|(_, v)| *v).collect();
+    assert_eq!(found, vec!["b"]);
+    let found: Vec<_> = tree.stabbing(30).map(|(_, v)| *v).collect();
+    assert_eq!(found, vec!["b", "f"]);
+    let found: Vec<_> = tree.stabbing(20).map(|(i, _)| (i.start, i.end)).collect();
+    assert_eq!(found, vec![(5, 20), (10, 30), (15, 20)]);
+    assert_eq!(tree.overlapping(41, 50).next(), None);
+    assert_eq!(tree.overlapping(0, 4).next(), None);
+
+    assert_eq!(tree.remove(&10, &30), Some("b"));
+    assert_eq!(tree.remove(&10, &30), None);
+    assert_eq!(tree.remove(&10, &31), None);
+    tree.assert_invariants();
+    assert_eq!(tree.overlapping(21, 23).next(), None);
+    let starts: Vec<_> = tree.iter().map(|(i, _)| i.start).collect();
+    assert_eq!(starts, vec![5, 12, 15, 17, 30]);
+}
+
+#[test]
+#[should_panic]
+fn test_interval_tree_reversed_interval() {
+    let mut tree = IntervalTree::new();
+    tree.insert(3, 2, ());
+}
+
+#[test]
+fn test_interval_tree_random() {
+    let mut tree = IntervalTree::new();
+    let mut expected: Vec<(Interval<u64>, u64)> = Vec::new();
+    let mut rng = XorShift64::new(13);
+    for round in 0..3000 {
+        let start = rng.next_below(200);
+        let end = start + rng.next_below(30);
+        if rng.next_below(3) == 1 {
+            let pos = expected.iter().position(|(i, _)| i.start == start && i.end == end);
+            let removed = tree.remove(&start, &end);
+            assert_eq!(removed.is_some(), pos.is_some());
+            if let Some(pos) = pos {
+                expected.remove(pos);
+            }
+        } else {
+            tree.insert(start, end, round);
+            expected.push((Interval::new(start, end), round));
+        }
+        if round % 50 == 49 {
+            tree.assert_invariants();
+            let (a, b) = (rng.next_below(230), rng.next_below(230));
+            let (a, b) = (a.min(b), a.max(b));
+            let mut found: Vec<_> = tree.overlapping(a, b).map(|(i, _)| i.clone()).collect();
+            assert!(found.windows(2).all(|w| w[0] <= w[1]));
+            let mut wanted: Vec<_> = expected.iter().map(|(i, _)| i.clone()).filter(|i| i.overlaps(&a, &b)).collect();
+            found.sort();
+            wanted.sort();
+            assert_eq!(found, wanted);
+            let stabbed = tree.stabbing(a).count();
+            assert_eq!(stabbed, expected.iter().filter(|(i, _)| i.contains(&a)).count());
+        }
+    }
+    assert_eq!(tree.len(), expected.len());
+    assert!(tree.height() <= 2 * 12);
+}
@@ -0,0 +1,311 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use rng::XorShift64;
+
+const DEFAULT_SEED: u64 = 0x5eed;
+
+type Link<K, V> = Option<Box<TreapNode<K, V>>>;
+
+// Max-heap on `priority`, BST on `key`; `size` counts the subtree.
+struct TreapNode<K, V> {
+    key: K,
+    value: V,
+    priority: u64,
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> TreapNode<K, V> {
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+// Joins two treaps where every key in `left` precedes every key in `right`.
+fn merge<K, V>(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+// Splits into keys `< key` and keys `>= key`.
+fn split<K: Ord, V>(link: Link<K, V>, key: &K) -> (Link<K, V>, Link<K, V>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if node.key < *key {
+                let (left, right) = split(node.right.take(), key);
+                node.right = left;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(node.left.take(), key);
+                node.left = right;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+// Splits into the first `n` entries and the rest.
+fn split_at<K, V>(link: Link<K, V>, n: usize) -> (Link<K, V>, Link<K, V>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            let left_size = size(&node.left);
+            if left_size < n {
+                let (left, right) = split_at(node.right.take(), n - left_size - 1);
+                node.right = left;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, right) = split_at(node.left.take(), n);
+                node.left = right;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+fn remove<K: Ord, V>(link: &mut Link<K, V>, key: &K) -> Option<V> {
+    let ordering = key.cmp(&link.as_ref()?.key);
+    if ordering == Ordering::Equal {
+        let mut node = link.take().unwrap();
+        *link = merge(node.left.take(), node.right.take());
+        return Some(node.value);
+    }
+    let node = link.as_mut().unwrap();
+    let removed = if ordering == Ordering::Less {
+        remove(&mut node.left, key)
+    } else {
+        remove(&mut node.right, key)
+    };
+    if removed.is_some() {
+        node.update();
+    }
+    removed
+}
+
+/// Ordered map backed by a treap: a BST whose shape is fixed by random
+/// node priorities, giving expected O(log n) operations. Splitting and
+/// concatenating whole treaps is also O(log n), which makes range cuts
+/// cheap.
+pub struct Treap<K, V> {
+    root: Link<K, V>,
+    rng: XorShift64,
+}
+
+impl<K: Ord, V> Treap<K, V> {
+    pub fn new() -> Treap<K, V> {
+        Treap::with_seed(DEFAULT_SEED)
+    }
+
+    /// Builds an empty treap whose priorities come from `seed`, so the
+    /// resulting shape is reproducible.
+    pub fn with_seed(seed: u64) -> Treap<K, V> {
+        Treap {
+            root: None,
+            rng: XorShift64::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn height(&self) -> usize {
+        fn height<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref().map_or(0, |node| 1 + ::std::cmp::max(height(&node.left), height(&node.right)))
+        }
+        height(&self.root)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match key.cmp(&node.key) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut link = &mut self.root;
+        while let Some(ref mut node) = *link {
+            link = match key.cmp(&node.key) {
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+                Ordering::Equal => return Some(&mut node.value),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(mem::replace(old, value));
+        }
+        let node = Box::new(TreapNode {
+            key,
+            value,
+            priority: self.rng.next_u64(),
+            size: 1,
+            left: None,
+            right: None,
+        });
+        let (left, right) = split(self.root.take(), &node.key);
+        self.root = merge(merge(left, Some(node)), right);
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        remove(&mut self.root, key)
+    }
+
+    /// The `index`-th entry in key order.
+    pub fn get_index(&self, mut index: usize) -> Option<(&K, &V)> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            let left_size = size(&node.left);
+            match index.cmp(&left_size) {
+                Ordering::Less => link = &node.left,
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    link = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    /// Moves every entry with a key `>= key` into a new treap.
+    pub fn split_off(&mut self, key: &K) -> Treap<K, V> {
+        let (left, right) = split(self.root.take(), key);
+        self.root = left;
+        self.detach(right)
+    }
+
+    /// Keeps the first `n` entries and returns the rest as a new treap.
+    pub fn split_at(&mut self, n: usize) -> Treap<K, V> {
+        let (left, right) = split_at(self.root.take(), n);
+        self.root = left;
+        self.detach(right)
+    }
+
+    fn detach(&mut self, root: Link<K, V>) -> Treap<K, V> {
+        Treap {
+            root,
+            rng: XorShift64::new(self.rng.next_u64()),
+        }
+    }
+
+    /// Concatenates `other` onto the end of `self`. Every key in `other`
+    /// must be greater than every key in `self`.
+    pub fn append(&mut self, other: &mut Treap<K, V>) {
+        if let (Some(last), Some(first)) = (self.last_key(), other.first_key()) {
+            assert!(last < first, "appended treap must follow this one in key order");
+        }
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    fn first_key(&self) -> Option<&K> {
+        self.get_index(0).map(|(k, _)| k)
+    }
+
+    fn last_key(&self) -> Option<&K> {
+        self.len().checked_sub(1).and_then(|i| self.get_index(i)).map(|(k, _)| k)
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> TreapIter<'_, K, V> {
+        let mut iter = TreapIter { stack: Vec::new() };
+        iter.push_left_spine(&self.root);
+        iter
+    }
+
+    /// Panics unless the keys are ordered, priorities form a max-heap and
+    /// subtree sizes are correct.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        fn check<K: Ord, V>(link: &Link<K, V>, lower: Option<&K>, upper: Option<&K>, parent: u64) -> usize {
+            let node = match *link {
+                Some(ref node) => node,
+                None => return 0,
+            };
+            assert!(lower.is_none_or(|lower| *lower < node.key), "keys out of order");
+            assert!(upper.is_none_or(|upper| node.key < *upper), "keys out of order");
+            assert!(node.priority <= parent, "heap order violated");
+            let size = 1 + check(&node.left, lower, Some(&node.key), node.priority)
+                + check(&node.right, Some(&node.key), upper, node.priority);
+            assert_eq!(node.size, size, "stale size");
+            size
+        }
+        check(&self.root, None, None, u64::MAX);
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a Treap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = TreapIter<'a, K, V>;
+
+    fn into_iter(self) -> TreapIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+pub struct TreapIter<'a, K: 'a, V: 'a> {
+    stack: Vec<&'a TreapNode<K, V>>,
+}
+
+impl<'a, K, V> TreapIter<'a, K, V> {
+    fn push_left_spine(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(ref node) = *link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for TreapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(&node.right);
+        Some((&node.key, &node.value))
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::Bound;
+use std::mem;
+use std::ops::{RangeBounds, RangeFull};
+
+use slab::Slab;
+
+use super::DEFAULT_ORDER;
+
+// Internal nodes only route: keys in `children[i]` are `>= keys[i - 1]` and
+// `< keys[i]`. All entries live in the leaves, which are chained left to
+// right through `next`.
+enum Node<K, V> {
+    Internal {
+        keys: Vec<K>,
+        children: Vec<usize>,
+    },
+    Leaf {
+        keys: Vec<K>,
+        values: Vec<V>,
+        next: Option<usize>,
+    },
+}
+
+impl<K, V> Node<K, V> {
+    fn keys(&self) -> &[K] {
+        match *self {
+            Node::Internal { ref keys, .. } | Node::Leaf { ref keys, .. } => keys,
+        }
+    }
+}
+
+/// B+-tree map over a slab of nodes. Sequential scans walk the leaf chain
+/// instead of climbing back up the tree.
+pub struct BPlusTree<K, V> {
+    nodes: Slab<Node<K, V>>,
+    root: usize,
+    order: usize,
+    len: usize,
+}
+
+impl<K: Ord + Clone, V> BPlusTree<K, V> {
+    pub fn new() -> BPlusTree<K, V> {
+        BPlusTree::with_order(DEFAULT_ORDER)
+    }
+
+    /// `order` is the maximum number of children of an internal node, and
+    /// one more than the maximum number of entries in a leaf.
+    pub fn with_order(order: usize) -> BPlusTree<K, V> {
+        assert!(order >= 3, "B+-tree order must be at least 3");
+        let mut tree = BPlusTree {
+            nodes: Slab::with_capacity(16),
+            root: 0,
+            order,
+            len: 0,
+        };
+        tree.root = tree.alloc(Node::Leaf {
+            keys: Vec::new(),
+            values: Vec::new(),
+            next: None,
+        });
+        tree
+    }
+
+    /// Builds a tree from entries sorted by strictly increasing key by
+    /// filling the leaves left to right and then stacking index levels on
+    /// top of them.
+    pub fn from_sorted<I>(order: usize, entries: I) -> BPlusTree<K, V>
+        where I: IntoIterator<Item = (K, V)> {
+        let mut tree = BPlusTree::with_order(order);
+        let entries: Vec<(K, V)> = entries.into_iter().collect();
+        assert!(entries.windows(2).all(|w| w[0].0 < w[1].0),
+                "bulk load input must be sorted by strictly increasing key");
+        if entries.is_empty() {
+            return tree;
+        }
+        tree.len = entries.len();
+        let empty_root = tree.root;
+        tree.nodes.remove(empty_root);
+
+        // Each level is a list of (smallest key, node) pairs.
+        let mut level = Vec::new();
+        let mut entries = entries.into_iter();
+        let mut prev: Option<usize> = None;
+        for size in even_split(tree.len, order - 1) {
+            let (keys, values): (Vec<K>, Vec<V>) = entries.by_ref().take(size).unzip();
+            let first = keys[0].clone();
+            let leaf = tree.alloc(Node::Leaf { keys, values, next: None });
+            if let Some(prev) = prev {
+                if let Node::Leaf { ref mut next, .. } = tree.nodes[prev] {
+                    *next = Some(leaf);
+                }
+            }
+            prev = Some(leaf);
+            level.push((first, leaf));
+        }
+        while level.len() > 1 {
+            let mut parents = Vec::new();
+            let mut level_iter = level.into_iter();
+            for size in even_split(level_iter.len(), order) {
+                let group: Vec<(K, usize)> = level_iter.by_ref().take(size).collect();
+                let first = group[0].0.clone();
+                let keys = group[1..].iter().map(|(key, _)| key.clone()).collect();
+                let children = group.into_iter().map(|(_, child)| child).collect();
+                parents.push((first, tree.alloc(Node::Internal { keys, children })));
+            }
+            level = parents;
+        }
+        tree.root = level[0].1;
+        tree
+    }
+
+    fn alloc(&mut self, node: Node<K, V>) -> usize {
+        match self.nodes.insert(node) {
+            Ok(idx) => idx,
+            Err(node) => {
+                let capacity = self.nodes.capacity();
+                self.nodes.reserve_exact(capacity.max(16));
+                match self.nodes.insert(node) {
+                    Ok(idx) => idx,
+                    Err(_) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut height = 1;
+        let mut n = self.root;
+        while let Node::Internal { ref children, .. } = self.nodes[n] {
+            n = children[0];
+            height += 1;
+        }
+        height
+    }
+
+    fn min_keys(&self) -> usize {
+        self.order.div_ceil(2) - 1
+    }
+
+    // Leaf whose key span covers `key`.
+    fn find_leaf(&self, key: &K) -> usize {
+        let mut n = self.root;
+        while let Node::Internal { ref keys, ref children } = self.nodes[n] {
+            n = children[child_index(keys, key)];
+        }
+        n
+    }
+
+    fn first_leaf(&self) -> usize {
+        let mut n = self.root;
+        while let Node::Internal { ref children, .. } = self.nodes[n] {
+            n = children[0];
+        }
+        n
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match self.nodes[self.find_leaf(key)] {
+            Node::Leaf { ref keys, ref values, .. } => {
+                keys.binary_search(key).ok().map(|i| &values[i])
+            },
+            Node::Internal { .. } => unreachable!(),
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let leaf = self.find_leaf(key);
+        match self.nodes[leaf] {
+            Node::Leaf { ref keys, ref mut values, .. } => {
+                match keys.binary_search(key) {
+                    Ok(i) => Some(&mut values[i]),
+                    Err(_) => None,
+                }
+            },
+            Node::Internal { .. } => unreachable!(),
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let root = self.root;
+        let (old, split) = self.insert_at(root, key, value);
+        if let Some((key, right)) = split {
+            self.root = self.alloc(Node::Internal {
+                keys: vec![key],
+                children: vec![root, right],
+            });
+        }
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    // Returns the replaced value and, if `n` overflowed, the separator and
+    // index of its new right sibling.
+    fn insert_at(&mut self, n: usize, key: K, value: V) -> (Option<V>, Option<(K, usize)>) {
+        let order = self.order;
+        let (old, split) = match self.nodes[n] {
+            Node::Leaf { ref mut keys, ref mut values, ref mut next } => {
+                match keys.binary_search(&key) {
+                    Ok(i) => return (Some(mem::replace(&mut values[i], value)), None),
+                    Err(i) => {
+                        keys.insert(i, key);
+                        values.insert(i, value);
+                    }
+                }
+                if keys.len() < order {
+                    return (None, None);
+                }
+                let mid = keys.len() / 2;
+                let right = Node::Leaf {
+                    keys: keys.split_off(mid),
+                    values: values.split_off(mid),
+                    next: next.take(),
+                };
+                (None, right)
+            },
+            Node::Internal { ref keys, ref children } => {
+                let i = child_index(keys, &key);
+                let child = children[i];
+                let (old, split) = self.insert_at(child, key, value);
+                let (key, right) = match split {
+                    Some(split) => split,
+                    None => return (old, None),
+                };
+                match self.nodes[n] {
+                    Node::Internal { ref mut keys, ref mut children } => {
+                        keys.insert(i, key);
+                        children.insert(i + 1, right);
+                        if children.len() <= order {
+                            return (old, None);
+                        }
+                        let mid = keys.len() / 2;
+                        let right_keys = keys.split_off(mid + 1);
+                        let right_children = children.split_off(mid + 1);
+                        let separator = keys.pop().unwrap();
+                        let right = Node::Internal {
+                            keys: right_keys,
+                            children: right_children,
+                        };
+                        let right = self.alloc(right);
+                        return (old, Some((separator, right)));
+                    },
+                    Node::Leaf { .. } => unreachable!(),
+                }
+            }
+        };
+        let separator = split.keys()[0].clone();
+        let right = self.alloc(split);
+        if let Node::Leaf { ref mut next, .. } = self.nodes[n] {
+            *next = Some(right);
+        }
+        (old, Some((separator, right)))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let root = self.root;
+        let removed = self.remove_at(root, key);
+        let collapse = match self.nodes[root] {
+            Node::Internal { ref children, .. } if children.len() == 1 => Some(children[0]),
+            _ => None,
+        };
+        if let Some(child) = collapse {
+            self.nodes.remove(root);
+            self.root = child;
+        }
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn remove_at(&mut self, n: usize, key: &K) -> Option<V> {
+        let (i, child) = match self.nodes[n] {
+            Node::Leaf { ref mut keys, ref mut values, .. } => {
+                return match keys.binary_search(key) {
+                    Ok(i) => {
+                        keys.remove(i);
+                        Some(values.remove(i))
+                    },
+                    Err(_) => None,
+                };
+            },
+            Node::Internal { ref keys, ref children } => {
+                let i = child_index(keys, key);
+                (i, children[i])
+            }
+        };
+        let removed = self.remove_at(child, key);
+        if removed.is_some() && self.nodes[child].keys().len() < self.min_keys() {
+            self.fix_child(n, i);
+        }
+        removed
+    }
+
+    fn children(&self, n: usize) -> &[usize] {
+        match self.nodes[n] {
+            Node::Internal { ref children, .. } => children,
+            Node::Leaf { .. } => unreachable!(),
+        }
+    }
+
+    fn separators(&mut self, n: usize) -> &mut Vec<K> {
+        match self.nodes[n] {
+            Node::Internal { ref mut keys, .. } => keys,
+            Node::Leaf { .. } => unreachable!(),
+        }
+    }
+
+    // Tops up the underfull `i`-th child of `parent` from a sibling, or
+    // merges it with one.
+    fn fix_child(&mut self, parent: usize, i: usize) {
+        let min_keys = self.min_keys();
+        let siblings = self.children(parent).len();
+        let child = self.children(parent)[i];
+        if i > 0 {
+            let left = self.children(parent)[i - 1];
+            if self.nodes[left].keys().len() > min_keys {
+                return self.borrow_from_left(parent, i, left, child);
+            }
+        }
+        if i + 1 < siblings {
+            let right = self.children(parent)[i + 1];
+            if self.nodes[right].keys().len() > min_keys {
+                return self.borrow_from_right(parent, i, child, right);
+            }
+        }
+        if i > 0 {
+            self.merge_children(parent, i - 1);
+        } else {
+            self.merge_children(parent, i);
+        }
+    }
+
+    fn borrow_from_left(&mut self, parent: usize, i: usize, left: usize, child: usize) {
+        let separator = self.separators(parent)[i - 1].clone();
+        let (key, moved) = match self.nodes[left] {
+            Node::Leaf { ref mut keys, ref mut values, .. } => {
+                (keys.pop().unwrap(), Moved::Value(values.pop().unwrap()))
+            },
+            Node::Internal { ref mut keys, ref mut children } => {
+                (keys.pop().unwrap(), Moved::Child(children.pop().unwrap()))
+            }
+        };
+        let new_separator = match (&mut self.nodes[child], moved) {
+            (&mut Node::Leaf { ref mut keys, ref mut values, .. }, Moved::Value(value)) => {
+                keys.insert(0, key.clone());
+                values.insert(0, value);
+                key
+            },
+            (&mut Node::Internal { ref mut keys, ref mut children }, Moved::Child(moved)) => {
+                keys.insert(0, separator);
+                children.insert(0, moved);
+                key
+            },
+            _ => unreachable!(),
+        };
+        self.separators(parent)[i - 1] = new_separator;
+    }
+
+    fn borrow_from_right(&mut self, parent: usize, i: usize, child: usize, right: usize) {
+        let separator = self.separators(parent)[i].clone();
+        let (key, moved, right_first) = match self.nodes[right] {
+            Node::Leaf { ref mut keys, ref mut values, .. } => {
+                let key = keys.remove(0);
+                (key, Moved::Value(values.remove(0)), keys[0].clone())
+            },
+            Node::Internal { ref mut keys, ref mut children } => {
+                let key = keys.remove(0);
+                (key.clone(), Moved::Child(children.remove(0)), key)
+            }
+        };
+        match (&mut self.nodes[child], moved) {
+            (&mut Node::Leaf { ref mut keys, ref mut values, .. }, Moved::Value(value)) => {
+                keys.push(key);
+                values.push(value);
+            },
+            (&mut Node::Internal { ref mut keys, ref mut children }, Moved::Child(moved)) => {
+                keys.push(separator);
+                children.push(moved);
+            },
+            _ => unreachable!(),
+        }
+        self.separators(parent)[i] = right_first;
+    }
+
+    // Folds the `(i + 1)`-th child of `parent` into the `i`-th.
+    fn merge_children(&mut self, parent: usize, i: usize) {
+        let separator = self.separators(parent).remove(i);
+        let right = match self.nodes[parent] {
+            Node::Internal { ref mut children, .. } => children.remove(i + 1),
+            Node::Leaf { .. } => unreachable!(),
+        };
+        let left = self.children(parent)[i];
+        let right = self.nodes.remove(right).unwrap();
+        match (&mut self.nodes[left], right) {
+            (&mut Node::Leaf { ref mut keys, ref mut values, ref mut next },
+             Node::Leaf { keys: right_keys, values: right_values, next: right_next }) => {
+                keys.extend(right_keys);
+                values.extend(right_values);
+                *next = right_next;
+            },
+            (&mut Node::Internal { ref mut keys, ref mut children },
+             Node::Internal { keys: right_keys, children: right_children }) => {
+                keys.push(separator);
+                keys.extend(right_keys);
+                children.extend(right_children);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// Iterates over the entries in ascending key order by walking the leaf
+    /// chain.
+    pub fn iter(&self) -> BPlusRange<'_, K, V, RangeFull> {
+        self.range(..)
+    }
+
+    /// Ascending iteration over the entries whose keys fall within `range`.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> BPlusRange<'_, K, V, R> {
+        let (leaf, pos) = {
+            let (leaf, keys) = match range.start_bound() {
+                Bound::Included(start) | Bound::Excluded(start) => {
+                    let leaf = self.find_leaf(start);
+                    (leaf, self.nodes[leaf].keys())
+                },
+                Bound::Unbounded => {
+                    let leaf = self.first_leaf();
+                    (leaf, self.nodes[leaf].keys())
+                }
+            };
+            let pos = match range.start_bound() {
+                Bound::Included(start) => keys.partition_point(|k| k < start),
+                Bound::Excluded(start) => keys.partition_point(|k| k <= start),
+                Bound::Unbounded => 0,
+            };
+            (leaf, pos)
+        };
+        BPlusRange {
+            tree: self,
+            leaf: Some(leaf),
+            pos,
+            range,
+        }
+    }
+
+    /// Panics unless the keys are ordered and routed correctly, all leaves
+    /// share one depth, node sizes respect the order and the leaf chain
+    /// visits every entry.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        let mut leaves = Vec::new();
+        let count = self.check_node(self.root, true, 0, &mut leaves, None, None);
+        assert_eq!(count, self.len);
+        assert!(leaves.windows(2).all(|w| w[0].1 == w[1].1), "leaves at different depths");
+
+        let mut chained = Vec::new();
+        let mut leaf = Some(self.first_leaf());
+        while let Some(n) = leaf {
+            chained.push(n);
+            leaf = match self.nodes[n] {
+                Node::Leaf { next, .. } => next,
+                Node::Internal { .. } => panic!("internal node in leaf chain"),
+            };
+        }
+        assert_eq!(chained, leaves.iter().map(|&(n, _)| n).collect::<Vec<_>>());
+        assert_eq!(self.nodes.len(), self.node_count(self.root), "leaked nodes");
+    }
+
+    #[cfg(test)]
+    fn check_node(&self, n: usize, is_root: bool, depth: usize, leaves: &mut Vec<(usize, usize)>,
+                  lower: Option<&K>, upper: Option<&K>) -> usize {
+        let keys = self.nodes[n].keys();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]), "keys out of order");
+        assert!(keys.first().is_none_or(|k| lower.is_none_or(|lower| lower <= k)));
+        assert!(keys.last().is_none_or(|k| upper.is_none_or(|upper| k < upper)));
+        match self.nodes[n] {
+            Node::Leaf { ref values, .. } => {
+                assert!(keys.len() < self.order, "leaf overflow");
+                assert!(is_root || keys.len() >= self.min_keys(), "leaf underflow");
+                assert_eq!(keys.len(), values.len());
+                leaves.push((n, depth));
+                keys.len()
+            },
+            Node::Internal { ref children, .. } => {
+                assert!(children.len() <= self.order, "node overflow");
+                assert!(is_root || keys.len() >= self.min_keys(), "node underflow");
+                assert!(!is_root || !keys.is_empty(), "internal root without separators");
+                assert_eq!(children.len(), keys.len() + 1);
+                let mut count = 0;
+                for (i, &child) in children.iter().enumerate() {
+                    let lower = if i == 0 { lower } else { Some(&keys[i - 1]) };
+                    let upper = keys.get(i).or(upper);
+                    count += self.check_node(child, false, depth + 1, leaves, lower, upper);
+                }
+                count
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn node_count(&self, n: usize) -> usize {
+        match self.nodes[n] {
+            Node::Leaf { .. } => 1,
+            Node::Internal { ref children, .. } => {
+                1 + children.iter().map(|&child| self.node_count(child)).sum::<usize>()
+            }
+        }
+    }
+}
+
+// What moves along with a key when borrowing from a sibling.
+enum Moved<V> {
+    Value(V),
+    Child(usize),
+}
+
+// Index of the child whose key span covers `key`.
+fn child_index<K: Ord>(keys: &[K], key: &K) -> usize {
+    keys.partition_point(|k| k <= key)
+}
+
+// Splits `len` items into the fewest groups of at most `max` items, with
+// group sizes differing by at most one.
+fn even_split(len: usize, max: usize) -> Vec<usize> {
+    let groups = len.div_ceil(max);
+    (0..groups)
+        .map(|i| len / groups + if i < len % groups { 1 } else { 0 })
+        .collect()
+}
+
+impl<'a, K: Ord + Clone, V> IntoIterator for &'a BPlusTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = BPlusRange<'a, K, V, RangeFull>;
+
+    fn into_iter(self) -> BPlusRange<'a, K, V, RangeFull> {
+        self.iter()
+    }
+}
+
+pub struct BPlusRange<'a, K: 'a, V: 'a, R> {
+    tree: &'a BPlusTree<K, V>,
+    leaf: Option<usize>,
+    pos: usize,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Iterator for BPlusRange<'a, K, V, R> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let tree = self.tree;
+        loop {
+            let (keys, values, next) = match tree.nodes[self.leaf?] {
+                Node::Leaf { ref keys, ref values, next } => (keys, values, next),
+                Node::Internal { .. } => unreachable!(),
+            };
+            if self.pos == keys.len() {
+                self.leaf = next;
+                self.pos = 0;
+                continue;
+            }
+            let key = &keys[self.pos];
+            let past_end = match self.range.end_bound() {
+                Bound::Included(end) => key > end,
+                Bound::Excluded(end) => key >= end,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                self.leaf = None;
+                return None;
+            }
+            self.pos += 1;
+            return Some((key, &values[self.pos - 1]));
+        }
+    }
+}
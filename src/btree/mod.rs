@@ -0,0 +1,421 @@
+use std::collections::Bound;
+use std::mem;
+use std::ops::{RangeBounds, RangeFull};
+
+mod bplus;
+
+pub use self::bplus::{BPlusTree, BPlusRange};
+
+pub const DEFAULT_ORDER: usize = 6;
+
+// A node with no children is a leaf; otherwise `children.len()` is always
+// `keys.len() + 1`.
+struct Node<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+    children: Vec<Node<K, V>>,
+}
+
+// Middle key and new right sibling of a node that overflowed.
+type Split<K, V> = Option<(K, V, Node<K, V>)>;
+
+impl<K: Ord, V> Node<K, V> {
+    fn new() -> Node<K, V> {
+        Node {
+            keys: Vec::new(),
+            values: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    // Splits an overflowing node around its middle key, returning that key
+    // and the new right sibling.
+    fn split(&mut self) -> (K, V, Node<K, V>) {
+        let mid = self.keys.len() / 2;
+        let mut right = Node::new();
+        right.keys = self.keys.split_off(mid + 1);
+        right.values = self.values.split_off(mid + 1);
+        if !self.is_leaf() {
+            right.children = self.children.split_off(mid + 1);
+        }
+        let key = self.keys.pop().unwrap();
+        let value = self.values.pop().unwrap();
+        (key, value, right)
+    }
+
+    fn insert(&mut self, key: K, value: V, order: usize) -> (Option<V>, Split<K, V>) {
+        let old = match self.keys.binary_search(&key) {
+            Ok(i) => return (Some(mem::replace(&mut self.values[i], value)), None),
+            Err(i) if self.is_leaf() => {
+                self.keys.insert(i, key);
+                self.values.insert(i, value);
+                None
+            },
+            Err(i) => {
+                let (old, split) = self.children[i].insert(key, value, order);
+                if let Some((key, value, right)) = split {
+                    self.keys.insert(i, key);
+                    self.values.insert(i, value);
+                    self.children.insert(i + 1, right);
+                }
+                old
+            }
+        };
+        if self.keys.len() >= order {
+            (old, Some(self.split()))
+        } else {
+            (old, None)
+        }
+    }
+
+    fn remove(&mut self, key: &K, min_keys: usize) -> Option<(K, V)> {
+        match self.keys.binary_search(key) {
+            Ok(i) if self.is_leaf() => Some((self.keys.remove(i), self.values.remove(i))),
+            // Replace the key with its in-order predecessor.
+            Ok(i) => {
+                let (key, value) = self.children[i].remove_max(min_keys);
+                let key = mem::replace(&mut self.keys[i], key);
+                let value = mem::replace(&mut self.values[i], value);
+                self.fix_child(i, min_keys);
+                Some((key, value))
+            },
+            Err(_) if self.is_leaf() => None,
+            Err(i) => {
+                let removed = self.children[i].remove(key, min_keys);
+                if removed.is_some() {
+                    self.fix_child(i, min_keys);
+                }
+                removed
+            }
+        }
+    }
+
+    fn remove_max(&mut self, min_keys: usize) -> (K, V) {
+        if self.is_leaf() {
+            (self.keys.pop().unwrap(), self.values.pop().unwrap())
+        } else {
+            let last = self.children.len() - 1;
+            let max = self.children[last].remove_max(min_keys);
+            self.fix_child(last, min_keys);
+            max
+        }
+    }
+
+    // Tops up `children[i]` after a removal left it with too few keys, by
+    // borrowing through the parent from a sibling or merging with one.
+    fn fix_child(&mut self, i: usize, min_keys: usize) {
+        if self.children[i].keys.len() >= min_keys {
+            return;
+        }
+        if i > 0 && self.children[i - 1].keys.len() > min_keys {
+            let (left, right) = self.children.split_at_mut(i);
+            let (left, child) = (&mut left[i - 1], &mut right[0]);
+            let key = mem::replace(&mut self.keys[i - 1], left.keys.pop().unwrap());
+            let value = mem::replace(&mut self.values[i - 1], left.values.pop().unwrap());
+            child.keys.insert(0, key);
+            child.values.insert(0, value);
+            if !left.is_leaf() {
+                child.children.insert(0, left.children.pop().unwrap());
+            }
+        } else if i + 1 < self.children.len() && self.children[i + 1].keys.len() > min_keys {
+            let (left, right) = self.children.split_at_mut(i + 1);
+            let (child, right) = (&mut left[i], &mut right[0]);
+            let key = mem::replace(&mut self.keys[i], right.keys.remove(0));
+            let value = mem::replace(&mut self.values[i], right.values.remove(0));
+            child.keys.push(key);
+            child.values.push(value);
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+        } else {
+            self.merge_children(if i > 0 { i - 1 } else { i });
+        }
+    }
+
+    // Folds `children[i + 1]` and the separator between them into
+    // `children[i]`.
+    fn merge_children(&mut self, i: usize) {
+        let right = self.children.remove(i + 1);
+        let key = self.keys.remove(i);
+        let value = self.values.remove(i);
+        let left = &mut self.children[i];
+        left.keys.push(key);
+        left.values.push(value);
+        left.keys.extend(right.keys);
+        left.values.extend(right.values);
+        left.children.extend(right.children);
+    }
+}
+
+/// In-memory B-tree map. Every node except the root holds between
+/// `ceil(order / 2) - 1` and `order - 1` keys, and all leaves sit at the same
+/// depth.
+pub struct BTree<K, V> {
+    root: Node<K, V>,
+    order: usize,
+    len: usize,
+}
+
+impl<K: Ord, V> BTree<K, V> {
+    pub fn new() -> BTree<K, V> {
+        BTree::with_order(DEFAULT_ORDER)
+    }
+
+    /// `order` is the maximum number of children per node.
+    pub fn with_order(order: usize) -> BTree<K, V> {
+        assert!(order >= 3, "B-tree order must be at least 3");
+        BTree {
+            root: Node::new(),
+            order,
+            len: 0,
+        }
+    }
+
+    /// Builds a tree from entries sorted by strictly increasing key, packing
+    /// the nodes directly instead of inserting one entry at a time.
+    pub fn from_sorted<I>(order: usize, entries: I) -> BTree<K, V>
+        where I: IntoIterator<Item = (K, V)> {
+        let mut tree = BTree::with_order(order);
+        let entries: Vec<(K, V)> = entries.into_iter().collect();
+        assert!(entries.windows(2).all(|w| w[0].0 < w[1].0),
+                "bulk load input must be sorted by strictly increasing key");
+
+        let len = entries.len();
+        let mut height = 1;
+        let mut capacity = order;
+        while capacity - 1 < len {
+            capacity = capacity.saturating_mul(order);
+            height += 1;
+        }
+        tree.root = build(&mut entries.into_iter(), len, height, order);
+        tree.len = len;
+        tree
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut height = 1;
+        let mut node = &self.root;
+        while !node.is_leaf() {
+            node = &node.children[0];
+            height += 1;
+        }
+        height
+    }
+
+    fn min_keys(&self) -> usize {
+        self.order.div_ceil(2) - 1
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = &self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&node.values[i]),
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => node = &node.children[i],
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&mut node.values[i]),
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => node = &mut node.children[i],
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (old, split) = self.root.insert(key, value, self.order);
+        if let Some((key, value, right)) = split {
+            let left = mem::replace(&mut self.root, Node::new());
+            self.root.keys.push(key);
+            self.root.values.push(value);
+            self.root.children = vec![left, right];
+        }
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let min_keys = self.min_keys();
+        let removed = self.root.remove(key, min_keys);
+        if self.root.keys.is_empty() && !self.root.is_leaf() {
+            self.root = self.root.children.pop().unwrap();
+        }
+        removed.map(|(_, value)| {
+            self.len -= 1;
+            value
+        })
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> Range<'_, K, V, RangeFull> {
+        self.range(..)
+    }
+
+    /// Ascending iteration over the entries whose keys fall within `range`.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, R> {
+        let mut stack = Vec::new();
+        let mut node = &self.root;
+        loop {
+            let i = match range.start_bound() {
+                Bound::Included(start) => node.keys.partition_point(|k| k < start),
+                Bound::Excluded(start) => node.keys.partition_point(|k| k <= start),
+                Bound::Unbounded => 0,
+            };
+            stack.push((node, i));
+            if node.is_leaf() {
+                break;
+            }
+            node = &node.children[i];
+        }
+        Range { stack, range }
+    }
+
+    /// Panics unless the keys are ordered, all leaves share one depth and
+    /// every node's key count respects the order.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        let mut leaf_depth = None;
+        let count = self.check_node(&self.root, true, 0, &mut leaf_depth, None, None);
+        assert_eq!(count, self.len);
+    }
+
+    #[cfg(test)]
+    fn check_node(&self, node: &Node<K, V>, is_root: bool, depth: usize,
+                  leaf_depth: &mut Option<usize>, lower: Option<&K>, upper: Option<&K>) -> usize {
+        assert!(node.keys.len() < self.order, "node overflow");
+        assert!(is_root || node.keys.len() >= self.min_keys(), "node underflow");
+        assert_eq!(node.keys.len(), node.values.len());
+        assert!(node.keys.windows(2).all(|w| w[0] < w[1]), "keys out of order");
+        assert!(node.keys.first().is_none_or(|k| lower.is_none_or(|lower| lower < k)));
+        assert!(node.keys.last().is_none_or(|k| upper.is_none_or(|upper| k < upper)));
+        if node.is_leaf() {
+            assert_eq!(*leaf_depth.get_or_insert(depth), depth, "leaves at different depths");
+            return node.keys.len();
+        }
+        assert_eq!(node.children.len(), node.keys.len() + 1);
+        let mut count = node.keys.len();
+        for (i, child) in node.children.iter().enumerate() {
+            let lower = if i == 0 { lower } else { Some(&node.keys[i - 1]) };
+            let upper = node.keys.get(i).or(upper);
+            count += self.check_node(child, false, depth + 1, leaf_depth, lower, upper);
+        }
+        count
+    }
+}
+
+// Builds a subtree of exactly `height` levels holding the next `len`
+// entries, spreading them as evenly as possible over the children.
+fn build<K: Ord, V, I>(entries: &mut I, len: usize, height: usize, order: usize) -> Node<K, V>
+    where I: Iterator<Item = (K, V)> {
+    let mut node = Node::new();
+    if height == 1 {
+        for (key, value) in entries.take(len) {
+            node.keys.push(key);
+            node.values.push(value);
+        }
+        return node;
+    }
+    // Each child subtree holds at most `order^(height - 1) - 1` entries.
+    let child_capacity = order.saturating_pow(height as u32 - 1);
+    let children = (len + 1).div_ceil(child_capacity);
+    let per_child = (len + 1 - children) / children;
+    let extra = (len + 1 - children) % children;
+    for i in 0..children {
+        let child_len = per_child + if i < extra { 1 } else { 0 };
+        node.children.push(build(entries, child_len, height - 1, order));
+        if i + 1 < children {
+            let (key, value) = entries.next().unwrap();
+            node.keys.push(key);
+            node.values.push(value);
+        }
+    }
+    node
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a BTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Range<'a, K, V, RangeFull>;
+
+    fn into_iter(self) -> Range<'a, K, V, RangeFull> {
+        self.iter()
+    }
+}
+
+// Each stack entry is a node and the index of its next key to yield; the
+// subtree left of that key has already been consumed or is on the stack.
+pub struct Range<'a, K: 'a, V: 'a, R> {
+    stack: Vec<(&'a Node<K, V>, usize)>,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Iterator for Range<'a, K, V, R> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            let (node, i) = *self.stack.last()?;
+            if i == node.keys.len() {
+                self.stack.pop();
+                continue;
+            }
+            self.stack.last_mut().unwrap().1 += 1;
+            if !node.is_leaf() {
+                let mut child = &node.children[i + 1];
+                loop {
+                    self.stack.push((child, 0));
+                    if child.is_leaf() {
+                        break;
+                    }
+                    child = &child.children[0];
+                }
+            }
+            let key = &node.keys[i];
+            let past_end = match self.range.end_bound() {
+                Bound::Included(end) => key > end,
+                Bound::Excluded(end) => key >= end,
+                Bound::Unbounded => false,
+            };
+            if past_end {
+                self.stack.clear();
+                return None;
+            }
+            return Some((key, &node.values[i]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use rng::XorShift64;
+
+use super::*;
+
+macro_rules! test_basic {
+    ($tree_type: ident) => {
+        let mut tree = $tree_type::with_order(3);
+        assert!(tree.is_empty());
+        assert_eq!(tree.get(&1), None);
+        assert_eq!(tree.remove(&1), None);
+
+        for i in 0..100 {
+            assert_eq!(tree.insert(i, i * 2), None);
+        }
+        tree.assert_invariants();
+        assert_eq!(tree.len(), 100);
+        assert_eq!(tree.insert(10, 0), Some(20));
+        assert_eq!(tree.get(&10), Some(&0));
+        *tree.get_mut(&11).unwrap() = 7;
+        assert_eq!(tree.get(&11), Some(&7));
+        assert!(tree.contains_key(&99));
+        assert!(!tree.contains_key(&100));
+
+        for i in (0..100).filter(|i| i % 3 != 0) {
+            assert!(tree.remove(&i).is_some());
+        }
+        tree.assert_invariants();
+        assert_eq!(tree.len(), 34);
+        assert_eq!(tree.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+                   (0..100).filter(|i| i % 3 == 0).collect::<Vec<_>>());
+        assert_eq!((&tree).into_iter().count(), 34);
+    }
+}
+
+#[test]
+fn test_btree_basic() {
+    test_basic!(BTree);
+}
+
+#[test]
+fn test_bplus_basic() {
+    test_basic!(BPlusTree);
+}
+
+macro_rules! test_random {
+    ($tree_type: ident) => {
+        for order in 3..9 {
+            let mut tree = $tree_type::with_order(order);
+            let mut expected = BTreeMap::new();
+            let mut rng = XorShift64::new(order as u64);
+            for round in 0..3000 {
+                let key = rng.next_below(500);
+                if rng.next_below(3) == 0 {
+                    assert_eq!(tree.remove(&key), expected.remove(&key));
+                } else {
+                    assert_eq!(tree.insert(key, round), expected.insert(key, round));
+                }
+                if round % 101 == 0 {
+                    tree.assert_invariants();
+                }
+            }
+            tree.assert_invariants();
+            assert!(tree.iter().eq(expected.iter()));
+            assert!(tree.range(100..200).eq(expected.range(100..200)));
+            assert!(tree.range(..=250).eq(expected.range(..=250)));
+            assert!(tree.range((Bound::Excluded(17), Bound::Unbounded))
+                    .eq(expected.range((Bound::Excluded(17), Bound::Unbounded))));
+
+            let keys: Vec<u64> = expected.keys().cloned().collect();
+            for key in keys {
+                assert_eq!(tree.remove(&key), expected.remove(&key));
+            }
+            tree.assert_invariants();
+            assert!(tree.is_empty());
+            assert_eq!(tree.height(), 0);
+        }
+    }
+}
+
+#[test]
+fn test_btree_random() {
+    test_random!(BTree);
+}
+
+#[test]
+fn test_bplus_random() {
+    test_random!(BPlusTree);
+}
+
+macro_rules! test_bulk_load {
+    ($tree_type: ident) => {
+        for order in 3..8 {
+            for len in (0..300).chain(vec![1000, 4095, 4096]) {
+                let mut tree = $tree_type::from_sorted(order, (0..len).map(|i| (i, i + 1)));
+                tree.assert_invariants();
+                assert_eq!(tree.len(), len);
+                assert!(tree.iter().map(|(k, v)| (*k, *v)).eq((0..len).map(|i| (i, i + 1))));
+
+                // The packed tree must stay valid under further updates.
+                tree.insert(len, 0);
+                if len > 0 {
+                    assert_eq!(tree.remove(&(len / 2)), Some(len / 2 + 1));
+                }
+                tree.assert_invariants();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_btree_bulk_load() {
+    test_bulk_load!(BTree);
+}
+
+#[test]
+fn test_bplus_bulk_load() {
+    test_bulk_load!(BPlusTree);
+}
+
+#[test]
+#[should_panic]
+fn test_bulk_load_unsorted() {
+    BTree::from_sorted(4, vec![(2, ()), (1, ())]);
+}
+
+#[test]
+fn test_range_scans() {
+    let btree = BTree::from_sorted(DEFAULT_ORDER, (0..1000).map(|i| (i * 2, ())));
+    let bplus = BPlusTree::from_sorted(DEFAULT_ORDER, (0..1000).map(|i| (i * 2, ())));
+
+    for tree_keys in [btree.range(11..21).map(|(k, _)| *k).collect::<Vec<_>>(),
+                          bplus.range(11..21).map(|(k, _)| *k).collect::<Vec<_>>()] {
+        assert_eq!(tree_keys, vec![12, 14, 16, 18, 20]);
+    }
+    assert_eq!(btree.range(2000..).next(), None);
+    assert_eq!(bplus.range(2000..).next(), None);
+    assert_eq!(btree.range(5..5).next(), None);
+    assert_eq!(bplus.range(..=0).count(), 1);
+    assert!(btree.height() <= 5);
+    assert!(bplus.height() <= 5);
+}
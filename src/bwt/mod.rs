@@ -0,0 +1,200 @@
+// bzip2-style preprocessing. The Burrows-Wheeler transform groups bytes that
+// appear in similar contexts, move-to-front turns those groups into runs of
+// small numbers, and run-length coding shortens the runs before the result
+// is Huffman coded.
+
+use std::error::Error;
+use std::fmt;
+
+use huffman_coding::{Huffman, HuffmanError};
+use suffix_array::suffix_array;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BwtError {
+    /// The Huffman stage failed to decode.
+    Huffman(HuffmanError),
+    /// The BWT primary index is outside the data.
+    BadIndex,
+    /// A run-length count follows fewer than four equal bytes.
+    BadRunLength,
+}
+
+impl fmt::Display for BwtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BwtError::Huffman(ref err) => write!(f, "huffman stage: {}", err),
+            BwtError::BadIndex => write!(f, "invalid bwt primary index"),
+            BwtError::BadRunLength => write!(f, "invalid run length"),
+        }
+    }
+}
+
+impl Error for BwtError {}
+
+impl From<HuffmanError> for BwtError {
+    fn from(err: HuffmanError) -> BwtError {
+        BwtError::Huffman(err)
+    }
+}
+
+/// Burrows-Wheeler transform of `data` followed by a virtual end marker that
+/// sorts before every byte. Returns the last column of the sorted rotations
+/// with the marker left out, and the row where the marker was.
+pub fn bwt(data: &[u8]) -> (Vec<u8>, usize) {
+    if data.is_empty() {
+        return (Vec::new(), 0);
+    }
+    // Row 0 is the rotation starting at the marker; the others follow the
+    // suffix array.
+    let mut last = Vec::with_capacity(data.len());
+    let mut primary = 0;
+    last.push(data[data.len() - 1]);
+    for (row, &pos) in suffix_array(data).iter().enumerate() {
+        if pos == 0 {
+            primary = row + 1;
+        } else {
+            last.push(data[pos - 1]);
+        }
+    }
+    (last, primary)
+}
+
+/// Undoes `bwt` by following the last-to-first column mapping.
+pub fn inverse_bwt(last: &[u8], primary: usize) -> Result<Vec<u8>, BwtError> {
+    let n = last.len();
+    if n == 0 {
+        return if primary == 0 { Ok(Vec::new()) } else { Err(BwtError::BadIndex) };
+    }
+    if primary == 0 || primary > n {
+        return Err(BwtError::BadIndex);
+    }
+    // The full last column, with the marker put back at `primary`.
+    let column = |row: usize| -> Option<u8> {
+        if row == primary {
+            None
+        } else if row < primary {
+            Some(last[row])
+        } else {
+            Some(last[row - 1])
+        }
+    };
+
+    // first[c]: the first row whose rotation starts with byte `c`; row 0
+    // starts with the marker.
+    let mut first = [0usize; 256];
+    for &byte in last {
+        first[byte as usize] += 1;
+    }
+    let mut sum = 1;
+    for slot in first.iter_mut() {
+        let count = *slot;
+        *slot = sum;
+        sum += count;
+    }
+
+    let mut lf = vec![0usize; n + 1];
+    let mut seen = [0usize; 256];
+    for (row, entry) in lf.iter_mut().enumerate() {
+        if let Some(byte) = column(row) {
+            *entry = first[byte as usize] + seen[byte as usize];
+            seen[byte as usize] += 1;
+        }
+    }
+
+    let mut out = vec![0u8; n];
+    let mut row = 0;
+    for k in (0..n).rev() {
+        out[k] = column(row).ok_or(BwtError::BadIndex)?;
+        row = lf[row];
+    }
+    Ok(out)
+}
+
+pub fn move_to_front_encode(data: &[u8]) -> Vec<u8> {
+    let mut order: Vec<u8> = (0..=255).collect();
+    data.iter()
+        .map(|&byte| {
+            let idx = order.iter().position(|&b| b == byte).unwrap();
+            order.remove(idx);
+            order.insert(0, byte);
+            idx as u8
+        })
+        .collect()
+}
+
+pub fn move_to_front_decode(data: &[u8]) -> Vec<u8> {
+    let mut order: Vec<u8> = (0..=255).collect();
+    data.iter()
+        .map(|&idx| {
+            let byte = order.remove(idx as usize);
+            order.insert(0, byte);
+            byte
+        })
+        .collect()
+}
+
+/// bzip2's initial run-length coding: after four equal bytes a count byte
+/// says how many more copies follow (0 to 251).
+pub fn run_length_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        let run = data[i..].iter().take(4 + 251).take_while(|&&b| b == byte).count();
+        if run >= 4 {
+            out.extend_from_slice(&[byte; 4]);
+            out.push((run - 4) as u8);
+        } else {
+            out.extend((0..run).map(|_| byte));
+        }
+        i += run;
+    }
+    out
+}
+
+pub fn run_length_decode(data: &[u8]) -> Result<Vec<u8>, BwtError> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        let run = data[i..].iter().take(4).take_while(|&&b| b == byte).count();
+        out.extend((0..run).map(|_| byte));
+        i += run;
+        if run == 4 {
+            let extra = *data.get(i).ok_or(BwtError::BadRunLength)?;
+            if extra > 251 {
+                return Err(BwtError::BadRunLength);
+            }
+            out.extend((0..extra).map(|_| byte));
+            i += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// BWT, move-to-front and run-length coding, then `Huffman::compress_to_vec`.
+/// The BWT primary index is stored in front as a u32 LE.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    assert!(data.len() < u32::MAX as usize, "input too large for a single block");
+    let (last, primary) = bwt(data);
+    let coded = run_length_encode(&move_to_front_encode(&last));
+
+    let primary = primary as u32;
+    let mut out = vec![primary as u8, (primary >> 8) as u8, (primary >> 16) as u8,
+                       (primary >> 24) as u8];
+    out.extend(Huffman::compress_to_vec(&coded));
+    out
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, BwtError> {
+    if data.len() < 4 {
+        return Err(BwtError::Huffman(HuffmanError::Truncated));
+    }
+    let primary = data[..4].iter().rev().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+    let coded = Huffman::decompress_from_slice(&data[4..])?;
+    let last = move_to_front_decode(&run_length_decode(&coded)?);
+    inverse_bwt(&last, primary)
+}
+
+#[cfg(test)]
+mod test;
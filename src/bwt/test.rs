@@ -0,0 +1,61 @@
+use huffman_coding::Huffman;
+
+use super::*;
+
+#[test]
+fn test_bwt_banana() {
+    // Rotations of "banana$" sorted: $banana, a$banan, ana$ban, anana$b,
+    // banana$, na$bana, nana$ba.
+    let (last, primary) = bwt(b"banana");
+    assert_eq!(last, b"annbaa".to_vec());
+    assert_eq!(primary, 4);
+    assert_eq!(inverse_bwt(&last, primary).unwrap(), b"banana".to_vec());
+}
+
+#[test]
+fn test_bwt_round_trip() {
+    let inputs: Vec<&[u8]> = vec![b"", b"a", b"aaaa", b"abracadabra", b"mississippi",
+                                  b"so much words wow many compression"];
+    for data in inputs {
+        let (last, primary) = bwt(data);
+        assert_eq!(last.len(), data.len());
+        assert_eq!(inverse_bwt(&last, primary).unwrap(), data.to_vec());
+    }
+    assert_eq!(inverse_bwt(b"abc", 0), Err(BwtError::BadIndex));
+    assert_eq!(inverse_bwt(b"abc", 4), Err(BwtError::BadIndex));
+}
+
+#[test]
+fn test_move_to_front() {
+    let encoded = move_to_front_encode(b"bananaaa");
+    assert_eq!(encoded, vec![98, 98, 110, 1, 1, 1, 0, 0]);
+    assert_eq!(move_to_front_decode(&encoded), b"bananaaa".to_vec());
+}
+
+#[test]
+fn test_run_length() {
+    let mut data = vec![1, 2, 2, 2, 2, 3];
+    data.extend(vec![9u8; 600]);
+    data.extend_from_slice(&[4, 4, 4]);
+    let encoded = run_length_encode(&data);
+    assert_eq!(&encoded[..7], &[1, 2, 2, 2, 2, 0, 3]);
+    assert!(encoded.len() < 30);
+    assert_eq!(run_length_decode(&encoded).unwrap(), data);
+
+    assert_eq!(run_length_decode(&[5, 5, 5, 5]), Err(BwtError::BadRunLength));
+    assert_eq!(run_length_decode(&[5, 5, 5, 5, 252]), Err(BwtError::BadRunLength));
+}
+
+#[test]
+fn test_pipeline() {
+    let mut text = Vec::new();
+    for i in 0..200 {
+        text.extend(format!("line {} of a fairly repetitive log file\n", i % 7).bytes());
+    }
+    let compressed = compress(&text);
+    assert_eq!(decompress(&compressed).unwrap(), text);
+    assert!(compressed.len() < Huffman::compress_to_vec(&text).len() / 3);
+
+    assert_eq!(decompress(&compress(b"x")).unwrap(), b"x".to_vec());
+    assert_eq!(decompress(&[1, 0]), Err(BwtError::Huffman(HuffmanError::Truncated)));
+}
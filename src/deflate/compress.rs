@@ -0,0 +1,262 @@
+use bitio::{BitWriter, BitOrder};
+use huffman_coding::{limited_code_lengths, canonical_codes};
+
+use super::lz77::{Token, tokenize};
+use super::{END_OF_BLOCK, LENGTH_BASE, LENGTH_EXTRA, DIST_BASE, DIST_EXTRA, CODE_LENGTH_ORDER,
+            MAX_LEVEL, base_index, fixed_lengths};
+
+// Tokens per block; every block gets codes fitted to its own statistics.
+const BLOCK_TOKENS: usize = 1 << 14;
+const MAX_STORED: usize = 65535;
+
+// A Huffman code ready for writing. DEFLATE sends code bits starting from
+// the most significant one into an LSB-first stream, so codes are kept
+// bit-reversed and written with a single `write_bits`.
+struct Code {
+    lengths: Vec<u8>,
+    reversed: Vec<u64>,
+}
+
+impl Code {
+    fn from_lengths(lengths: Vec<u8>) -> Code {
+        let codes = canonical_codes(&lengths).expect("lengths form a prefix code");
+        let reversed = codes.iter()
+            .zip(&lengths)
+            .map(|(&code, &len)| (code.reverse_bits() >> (32 - len.max(1) as u32)) as u64)
+            .collect();
+        Code { lengths, reversed }
+    }
+
+    // Lengths for `freqs` capped at `max_len`. At least two symbols get a
+    // code, since some inflaters reject a lone code in a complete tree.
+    fn fitted(freqs: &[usize], max_len: u8) -> Code {
+        let mut freqs = freqs.to_vec();
+        for symbol in 0..2 {
+            if freqs.iter().filter(|&&f| f > 0).count() < 2 && freqs[symbol] == 0 {
+                freqs[symbol] = 1;
+            }
+        }
+        Code::from_lengths(limited_code_lengths(&freqs, max_len))
+    }
+
+    fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write_bits(self.reversed[symbol], self.lengths[symbol] as u32);
+    }
+
+    fn cost(&self, freqs: &[usize]) -> usize {
+        freqs.iter().zip(&self.lengths).map(|(&f, &len)| f * len as usize).sum()
+    }
+}
+
+// Symbol counts of a block, plus the extra bits its lengths and distances
+// need on top of their codes.
+struct Stats {
+    lit: Vec<usize>,
+    dist: Vec<usize>,
+    extra_bits: usize,
+}
+
+fn stats(tokens: &[Token]) -> Stats {
+    let mut stats = Stats { lit: vec![0; 286], dist: vec![0; 30], extra_bits: 0 };
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => stats.lit[byte as usize] += 1,
+            Token::Match { length, distance } => {
+                let l = base_index(&LENGTH_BASE, length as usize);
+                let d = base_index(&DIST_BASE, distance as usize);
+                stats.lit[257 + l] += 1;
+                stats.dist[d] += 1;
+                stats.extra_bits += (LENGTH_EXTRA[l] + DIST_EXTRA[d]) as usize;
+            }
+        }
+    }
+    stats.lit[END_OF_BLOCK] += 1;
+    stats
+}
+
+fn write_tokens(writer: &mut BitWriter, tokens: &[Token], lit: &Code, dist: &Code) {
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => lit.write(writer, byte as usize),
+            Token::Match { length, distance } => {
+                let l = base_index(&LENGTH_BASE, length as usize);
+                lit.write(writer, 257 + l);
+                writer.write_bits((length - LENGTH_BASE[l]) as u64, LENGTH_EXTRA[l] as u32);
+                let d = base_index(&DIST_BASE, distance as usize);
+                dist.write(writer, d);
+                writer.write_bits((distance - DIST_BASE[d]) as u64, DIST_EXTRA[d] as u32);
+            }
+        }
+    }
+    lit.write(writer, END_OF_BLOCK);
+}
+
+// Run-length codes the literal/length and distance code lengths with the
+// code length alphabet: 16 repeats the previous length 3-6 times, 17 and 18
+// emit runs of 3-10 and 11-138 zeros. Items are (symbol, extra, extra bits).
+fn run_length_code(lengths: &[u8]) -> Vec<(usize, u64, u32)> {
+    let mut items = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let len = lengths[i];
+        let mut run = lengths[i..].iter().take_while(|&&l| l == len).count();
+        i += run;
+        if len == 0 {
+            while run >= 11 {
+                let n = run.min(138);
+                items.push((18, (n - 11) as u64, 7));
+                run -= n;
+            }
+            if run >= 3 {
+                items.push((17, (run - 3) as u64, 3));
+                run = 0;
+            }
+        } else {
+            items.push((len as usize, 0, 0));
+            run -= 1;
+            while run >= 3 {
+                let n = run.min(6);
+                items.push((16, (n - 3) as u64, 2));
+                run -= n;
+            }
+        }
+        for _ in 0..run {
+            items.push((len as usize, 0, 0));
+        }
+    }
+    items
+}
+
+struct DynamicHeader {
+    lit: Code,
+    dist: Code,
+    hlit: usize,
+    hdist: usize,
+    hclen: usize,
+    code_length_code: Code,
+    items: Vec<(usize, u64, u32)>,
+}
+
+impl DynamicHeader {
+    fn new(stats: &Stats) -> DynamicHeader {
+        let lit = Code::fitted(&stats.lit, 15);
+        let dist = Code::fitted(&stats.dist, 15);
+        let hlit = 257.max(lit.lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+        let hdist = 1.max(dist.lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+
+        let mut all = lit.lengths[..hlit].to_vec();
+        all.extend_from_slice(&dist.lengths[..hdist]);
+        let items = run_length_code(&all);
+        let mut cl_freqs = vec![0; 19];
+        for &(symbol, _, _) in &items {
+            cl_freqs[symbol] += 1;
+        }
+        let code_length_code = Code::fitted(&cl_freqs, 7);
+        let hclen = 4.max(CODE_LENGTH_ORDER.iter()
+            .rposition(|&s| code_length_code.lengths[s] > 0)
+            .unwrap_or(0) + 1);
+
+        DynamicHeader { lit, dist, hlit, hdist, hclen, code_length_code, items }
+    }
+
+    fn cost(&self) -> usize {
+        let items: usize = self.items.iter()
+            .map(|&(s, _, bits)| self.code_length_code.lengths[s] as usize + bits as usize)
+            .sum();
+        5 + 5 + 4 + 3 * self.hclen + items
+    }
+
+    fn write(&self, writer: &mut BitWriter) {
+        writer.write_bits((self.hlit - 257) as u64, 5);
+        writer.write_bits((self.hdist - 1) as u64, 5);
+        writer.write_bits((self.hclen - 4) as u64, 4);
+        for &symbol in &CODE_LENGTH_ORDER[..self.hclen] {
+            writer.write_bits(self.code_length_code.lengths[symbol] as u64, 3);
+        }
+        for &(symbol, extra, bits) in &self.items {
+            self.code_length_code.write(writer, symbol);
+            writer.write_bits(extra, bits);
+        }
+    }
+}
+
+fn write_stored(writer: &mut BitWriter, data: &[u8], last: bool) {
+    let mut chunks = data.chunks(MAX_STORED).peekable();
+    if chunks.peek().is_none() {
+        writer.write_bits(last as u64, 1);
+        writer.write_bits(0, 2);
+        writer.flush();
+        writer.write_bits(0x0000, 16);
+        writer.write_bits(0xffff, 16);
+        return;
+    }
+    while let Some(chunk) = chunks.next() {
+        writer.write_bits((last && chunks.peek().is_none()) as u64, 1);
+        writer.write_bits(0, 2);
+        // Stored data starts on a byte boundary.
+        writer.flush();
+        writer.write_bits(chunk.len() as u64, 16);
+        writer.write_bits(!chunk.len() as u64 & 0xffff, 16);
+        for &byte in chunk {
+            writer.write_bits(byte as u64, 8);
+        }
+    }
+}
+
+// Writes one block in whichever of the three block types is smallest.
+fn write_block(writer: &mut BitWriter, tokens: &[Token], data: &[u8], last: bool) {
+    let stats = stats(tokens);
+    let dynamic = DynamicHeader::new(&stats);
+    let (fixed_lit, fixed_dist) = fixed_lengths();
+    let fixed_lit = Code::from_lengths(fixed_lit);
+    let fixed_dist = Code::from_lengths(fixed_dist);
+
+    let dynamic_cost = 3 + dynamic.cost() + dynamic.lit.cost(&stats.lit) +
+                       dynamic.dist.cost(&stats.dist) + stats.extra_bits;
+    let fixed_cost = 3 + fixed_lit.cost(&stats.lit) + fixed_dist.cost(&stats.dist) +
+                     stats.extra_bits;
+    let stored_cost = (data.len() / MAX_STORED + 1) * (3 + 7 + 32) + 8 * data.len();
+
+    if stored_cost <= dynamic_cost && stored_cost <= fixed_cost {
+        write_stored(writer, data, last);
+    } else if fixed_cost <= dynamic_cost {
+        writer.write_bits(last as u64, 1);
+        writer.write_bits(1, 2);
+        write_tokens(writer, tokens, &fixed_lit, &fixed_dist);
+    } else {
+        writer.write_bits(last as u64, 1);
+        writer.write_bits(2, 2);
+        dynamic.write(writer);
+        write_tokens(writer, tokens, &dynamic.lit, &dynamic.dist);
+    }
+}
+
+fn token_len(token: &Token) -> usize {
+    match *token {
+        Token::Literal(_) => 1,
+        Token::Match { length, .. } => length as usize,
+    }
+}
+
+/// Compresses `data` into a raw DEFLATE stream at `level` (0 to `MAX_LEVEL`).
+pub fn compress(data: &[u8], level: u32) -> Vec<u8> {
+    assert!(level <= MAX_LEVEL, "level must be in 0..={}", MAX_LEVEL);
+    let mut writer = BitWriter::with_order(BitOrder::Lsb);
+    if level == 0 {
+        write_stored(&mut writer, data, true);
+        return writer.into_bytes();
+    }
+
+    let tokens = tokenize(data, level);
+    if tokens.is_empty() {
+        write_block(&mut writer, &tokens, data, true);
+    }
+    let mut start = 0;
+    let mut blocks = tokens.chunks(BLOCK_TOKENS).peekable();
+    while let Some(block) = blocks.next() {
+        let len: usize = block.iter().map(token_len).sum();
+        write_block(&mut writer, block, &data[start..start + len], blocks.peek().is_none());
+        start += len;
+    }
+    writer.into_bytes()
+}
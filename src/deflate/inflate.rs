@@ -0,0 +1,150 @@
+use bitio::{BitReader, BitOrder};
+use huffman_coding::canonical_codes;
+
+use super::{InflateError, END_OF_BLOCK, LENGTH_BASE, LENGTH_EXTRA, DIST_BASE, DIST_EXTRA,
+            CODE_LENGTH_ORDER, fixed_lengths};
+
+// Canonical decoding tables: the number of codes of every length and the
+// symbols sorted by (length, symbol), as in `CanonicalHuffman`.
+struct Decoder {
+    counts: [usize; 16],
+    symbols: Vec<u16>,
+}
+
+impl Decoder {
+    fn new(lengths: &[u8]) -> Result<Decoder, InflateError> {
+        if lengths.iter().any(|&len| len > 15) || canonical_codes(lengths).is_none() {
+            return Err(InflateError::BadCodeLengths);
+        }
+        let mut counts = [0; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16)
+            .filter(|&s| lengths[s as usize] > 0)
+            .collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Ok(Decoder { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<usize, InflateError> {
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+        for len in 1..16 {
+            code |= reader.read_bit()? as usize;
+            let count = self.counts[len];
+            if code - first < count {
+                return Ok(self.symbols[index + code - first] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(InflateError::BadSymbol)
+    }
+}
+
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Decoder, Decoder), InflateError> {
+    let hlit = reader.read_bits(5)? as usize + 257;
+    let hdist = reader.read_bits(5)? as usize + 1;
+    let hclen = reader.read_bits(4)? as usize + 4;
+    if hlit > 286 || hdist > 30 {
+        return Err(InflateError::BadCodeLengths);
+    }
+
+    let mut cl_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..hclen] {
+        cl_lengths[symbol] = reader.read_bits(3)? as u8;
+    }
+    let cl_decoder = Decoder::new(&cl_lengths)?;
+
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let (len, repeat) = match cl_decoder.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let prev = *lengths.last().ok_or(InflateError::BadCodeLengths)?;
+                (prev, 3 + reader.read_bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.read_bits(3)? as usize),
+            _ => (0, 11 + reader.read_bits(7)? as usize),
+        };
+        if lengths.len() + repeat > hlit + hdist {
+            return Err(InflateError::BadCodeLengths);
+        }
+        lengths.extend((0..repeat).map(|_| len));
+    }
+    if lengths[END_OF_BLOCK] == 0 {
+        return Err(InflateError::BadCodeLengths);
+    }
+    Ok((Decoder::new(&lengths[..hlit])?, Decoder::new(&lengths[hlit..])?))
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, lit: &Decoder, dist: &Decoder)
+                 -> Result<(), InflateError> {
+    loop {
+        let symbol = lit.decode(reader)?;
+        if symbol < END_OF_BLOCK {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == END_OF_BLOCK {
+            return Ok(());
+        }
+        let l = symbol - 257;
+        if l >= LENGTH_BASE.len() {
+            return Err(InflateError::BadSymbol);
+        }
+        let length = LENGTH_BASE[l] as usize + reader.read_bits(LENGTH_EXTRA[l] as u32)? as usize;
+        let d = dist.decode(reader)?;
+        if d >= DIST_BASE.len() {
+            return Err(InflateError::BadSymbol);
+        }
+        let distance = DIST_BASE[d] as usize + reader.read_bits(DIST_EXTRA[d] as u32)? as usize;
+        if distance > out.len() {
+            return Err(InflateError::BadDistance);
+        }
+        // Byte by byte, since the source may overlap what is being written.
+        let start = out.len() - distance;
+        for i in 0..length {
+            let byte = out[start + i];
+            out.push(byte);
+        }
+    }
+}
+
+/// Decompresses a raw DEFLATE stream.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, InflateError> {
+    let mut reader = BitReader::with_order(data, BitOrder::Lsb);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.read_bit()?;
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let len = reader.read_bits(16)?;
+                let nlen = reader.read_bits(16)?;
+                if len != !nlen & 0xffff {
+                    return Err(InflateError::BadStoredLength);
+                }
+                for _ in 0..len {
+                    out.push(reader.read_bits(8)? as u8);
+                }
+            }
+            1 => {
+                let (lit, dist) = fixed_lengths();
+                inflate_block(&mut reader, &mut out, &Decoder::new(&lit)?, &Decoder::new(&dist)?)?;
+            }
+            2 => {
+                let (lit, dist) = read_dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &lit, &dist)?;
+            }
+            _ => return Err(InflateError::BadBlockType),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::{MIN_MATCH, MAX_MATCH, WINDOW_SIZE, MAX_LEVEL};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Literal(u8),
+    /// Copy `length` bytes starting `distance` bytes back.
+    Match { length: u16, distance: u16 },
+}
+
+const HASH_BITS: usize = 15;
+const WINDOW_MASK: usize = WINDOW_SIZE - 1;
+
+// Search effort per level: how many chain links to follow, the match length
+// that ends the search early, and whether to try lazy matching.
+struct Params {
+    max_chain: usize,
+    nice_length: usize,
+    lazy: bool,
+}
+
+fn params(level: u32) -> Params {
+    let (max_chain, nice_length, lazy) = match level {
+        1 => (4, 8, false),
+        2 => (8, 16, false),
+        3 => (16, 32, false),
+        4 => (16, 32, true),
+        5 => (32, 64, true),
+        6 => (128, 128, true),
+        7 => (256, 128, true),
+        8 => (1024, 258, true),
+        _ => (4096, 258, true),
+    };
+    Params { max_chain, nice_length, lazy }
+}
+
+// Hash chains over the positions seen so far. `head` holds the most recent
+// position with each 3-byte hash and `prev` links every position to the
+// previous one with the same hash; both store `pos + 1` so 0 means none.
+struct MatchFinder<'a> {
+    data: &'a [u8],
+    head: Vec<usize>,
+    prev: Vec<usize>,
+    inserted: usize,
+}
+
+impl<'a> MatchFinder<'a> {
+    fn new(data: &'a [u8]) -> MatchFinder<'a> {
+        MatchFinder {
+            data,
+            head: vec![0; 1 << HASH_BITS],
+            prev: vec![0; WINDOW_SIZE],
+            inserted: 0,
+        }
+    }
+
+    fn hash(&self, pos: usize) -> usize {
+        let d = self.data;
+        let h = ((d[pos] as usize) << 10) ^ ((d[pos + 1] as usize) << 5) ^ d[pos + 2] as usize;
+        h & ((1 << HASH_BITS) - 1)
+    }
+
+    // Adds every position before `pos` to the chains.
+    fn insert_until(&mut self, pos: usize) {
+        while self.inserted < pos {
+            let p = self.inserted;
+            if p + MIN_MATCH <= self.data.len() {
+                let h = self.hash(p);
+                self.prev[p & WINDOW_MASK] = self.head[h];
+                self.head[h] = p + 1;
+            }
+            self.inserted += 1;
+        }
+    }
+
+    // Longest earlier match for the bytes at `pos`, as (length, distance).
+    fn longest_match(&mut self, pos: usize, params: &Params) -> (usize, usize) {
+        self.insert_until(pos);
+        let data = self.data;
+        let max_len = MAX_MATCH.min(data.len() - pos);
+        if max_len < MIN_MATCH {
+            return (0, 0);
+        }
+
+        let mut best = (0, 0);
+        let mut candidate = self.head[self.hash(pos)];
+        let mut chain = params.max_chain;
+        while candidate > 0 && chain > 0 {
+            let p = candidate - 1;
+            if pos - p > WINDOW_SIZE {
+                break;
+            }
+            let len = data[p..p + max_len]
+                .iter()
+                .zip(&data[pos..pos + max_len])
+                .take_while(|&(a, b)| a == b)
+                .count();
+            if len > best.0 {
+                best = (len, pos - p);
+                if len >= params.nice_length || len == max_len {
+                    break;
+                }
+            }
+            let next = self.prev[p & WINDOW_MASK];
+            // A slot reused by a newer position ends this chain.
+            if next == 0 || next > p {
+                break;
+            }
+            candidate = next;
+            chain -= 1;
+        }
+        if best.0 >= MIN_MATCH { best } else { (0, 0) }
+    }
+}
+
+/// Splits `data` into literals and back-references using hash chains, with
+/// more effort spent on higher `level`s (1 to `MAX_LEVEL`).
+pub fn tokenize(data: &[u8], level: u32) -> Vec<Token> {
+    assert!((1..=MAX_LEVEL).contains(&level), "level must be in 1..={}", MAX_LEVEL);
+    let params = params(level);
+    let mut finder = MatchFinder::new(data);
+    let mut tokens = Vec::new();
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let (len, dist) = finder.longest_match(pos, &params);
+        if len == 0 {
+            tokens.push(Token::Literal(data[pos]));
+            pos += 1;
+            continue;
+        }
+        // Lazy matching: if the next position starts a longer match, emit a
+        // literal now and take that match instead.
+        if params.lazy && len < params.nice_length && pos + 1 < data.len() {
+            let (next_len, _) = finder.longest_match(pos + 1, &params);
+            if next_len > len {
+                tokens.push(Token::Literal(data[pos]));
+                pos += 1;
+                continue;
+            }
+        }
+        tokens.push(Token::Match { length: len as u16, distance: dist as u16 });
+        pos += len;
+    }
+    tokens
+}
@@ -0,0 +1,103 @@
+// DEFLATE-style compression: an LZ77 match finder turns the input into
+// literals and (length, distance) back-references, which are then entropy
+// coded with length-limited canonical Huffman codes from `huffman_coding`.
+// The output is a raw DEFLATE stream (RFC 1951), so standard inflaters such
+// as zlib can read it and `decompress` can read theirs.
+
+use std::error::Error;
+use std::fmt;
+
+use bitio::BitIoError;
+
+mod lz77;
+mod compress;
+mod inflate;
+
+pub use self::lz77::{Token, tokenize};
+pub use self::compress::compress;
+pub use self::inflate::decompress;
+
+pub const MIN_MATCH: usize = 3;
+pub const MAX_MATCH: usize = 258;
+pub const WINDOW_SIZE: usize = 32768;
+
+/// Compression levels follow zlib: 0 stores the data uncompressed, 1 is the
+/// fastest and 9 searches hardest for matches.
+pub const MAX_LEVEL: u32 = 9;
+
+const END_OF_BLOCK: usize = 256;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43,
+                                51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4,
+                                4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385,
+                              513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385,
+                              24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9,
+                              10, 10, 11, 11, 12, 12, 13, 13];
+
+// Order in which the code length code lengths are transmitted.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2,
+                                        14, 1, 15];
+
+// Index of the largest base not above `value`.
+fn base_index(bases: &[u16], value: usize) -> usize {
+    match bases.binary_search(&(value as u16)) {
+        Ok(idx) => idx,
+        Err(idx) => idx - 1,
+    }
+}
+
+// Code lengths of the fixed Huffman codes from RFC 1951, section 3.2.6.
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+    let mut lit = vec![8u8; 288];
+    for len in &mut lit[144..256] {
+        *len = 9;
+    }
+    for len in &mut lit[256..280] {
+        *len = 7;
+    }
+    (lit, vec![5u8; 30])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InflateError {
+    /// The stream ended in the middle of a block.
+    Truncated,
+    /// A block header uses the reserved block type 3.
+    BadBlockType,
+    /// A stored block's length does not match its complement.
+    BadStoredLength,
+    /// A dynamic block describes an invalid set of Huffman codes.
+    BadCodeLengths,
+    /// The data contains a code that is not part of the block's code.
+    BadSymbol,
+    /// A back-reference points before the start of the output.
+    BadDistance,
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            InflateError::Truncated => "deflate stream is truncated",
+            InflateError::BadBlockType => "invalid block type",
+            InflateError::BadStoredLength => "stored block length mismatch",
+            InflateError::BadCodeLengths => "invalid code lengths",
+            InflateError::BadSymbol => "invalid huffman code",
+            InflateError::BadDistance => "distance too far back",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl Error for InflateError {}
+
+impl From<BitIoError> for InflateError {
+    fn from(_: BitIoError) -> InflateError {
+        InflateError::Truncated
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,116 @@
+use rng::XorShift64;
+
+use super::{compress, decompress, tokenize, Token, InflateError, MAX_LEVEL};
+
+fn bottles() -> Vec<u8> {
+    let mut text = Vec::new();
+    for i in (1..21).rev() {
+        text.extend(format!("{} bottles of beer on the wall, {} bottles of beer.\n", i, i).bytes());
+    }
+    text
+}
+
+fn pseudo_random(len: usize) -> Vec<u8> {
+    let mut rng = XorShift64::new(42);
+    (0..len).map(|_| rng.next_below(256) as u8).collect()
+}
+
+fn expand(tokens: &[Token]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => out.push(byte),
+            Token::Match { length, distance } => {
+                let start = out.len() - distance as usize;
+                for i in 0..length as usize {
+                    let byte = out[start + i];
+                    out.push(byte);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_tokenize() {
+    let data = b"abcabcabcabcxyz abcabc";
+    let tokens = tokenize(data, 6);
+    assert_eq!(&tokens[..4], &[Token::Literal(b'a'), Token::Literal(b'b'), Token::Literal(b'c'),
+                               Token::Match { length: 9, distance: 3 }]);
+    assert_eq!(expand(&tokens), data.to_vec());
+
+    let long = vec![7u8; 1000];
+    let tokens = tokenize(&long, 1);
+    assert_eq!(tokens[1], Token::Match { length: 258, distance: 1 });
+    assert_eq!(expand(&tokens), long);
+}
+
+#[test]
+fn test_round_trip_all_levels() {
+    let mut text = bottles();
+    text.extend(pseudo_random(2000));
+    text.extend(bottles());
+    let inputs = vec![Vec::new(), b"a".to_vec(), text, pseudo_random(70000), vec![0u8; 100000]];
+
+    for data in &inputs {
+        for level in 0..MAX_LEVEL + 1 {
+            let compressed = compress(data, level);
+            assert_eq!(&decompress(&compressed).unwrap(), data, "level {}", level);
+        }
+    }
+}
+
+#[test]
+fn test_levels_trade_speed_for_ratio() {
+    let mut data = Vec::new();
+    for _ in 0..20 {
+        data.extend(bottles());
+        data.extend(pseudo_random(300));
+    }
+    let stored = compress(&data, 0).len();
+    let fast = compress(&data, 1).len();
+    let best = compress(&data, 9).len();
+    assert!(stored > data.len());
+    assert!(fast < stored / 2);
+    assert!(best <= fast);
+}
+
+#[test]
+fn test_random_data_falls_back_to_stored() {
+    let data = pseudo_random(5000);
+    let compressed = compress(&data, 6);
+    assert!(compressed.len() <= data.len() + 10);
+}
+
+#[test]
+fn test_inflate_zlib_output() {
+    // zlib.compressobj(9, zlib.DEFLATED, -15) output: fixed Huffman blocks.
+    let fixed = [0x4b, 0x4c, 0x4a, 0x06, 0x00];
+    assert_eq!(decompress(&fixed).unwrap(), b"abc".to_vec());
+
+    // The same for `bottles()`, which zlib codes as a dynamic block.
+    let dynamic = [
+        0x85, 0xd2, 0x3b, 0x0a, 0x80, 0x30, 0x14, 0x44, 0xd1, 0xde, 0x55, 0xbc, 0x05, 0x88, 0x98,
+        0xf8, 0x5f, 0x8e, 0x42, 0xc4, 0x22, 0x18, 0xd0, 0x80, 0xdb, 0xd7, 0x3e, 0x83, 0xb7, 0x1e,
+        0x4e, 0x75, 0xc7, 0xb7, 0xb6, 0xa5, 0x9c, 0x63, 0xb8, 0x2d, 0xed, 0xb6, 0x85, 0x70, 0x59,
+        0x3a, 0x2d, 0x1f, 0xc1, 0x9e, 0x35, 0xc6, 0xda, 0x7c, 0xb1, 0x37, 0x95, 0x5b, 0xfe, 0x4d,
+        0xb9, 0x7f, 0x66, 0x06, 0x33, 0x0b, 0x33, 0x81, 0x99, 0x84, 0x19, 0xc1, 0x8c, 0xc2, 0x0c,
+        0x60, 0x06, 0x61, 0x7a, 0x30, 0xbd, 0x30, 0x1d, 0x98, 0x4e, 0x18, 0x0f, 0xc6, 0x0b, 0xe3,
+        0xc0, 0x38, 0x61, 0xe0, 0x07, 0x4e, 0xfc, 0x00, 0x6e, 0x20, 0x5e, 0x00, 0x27, 0x10, 0x1f,
+        0x80, 0x0b, 0x88, 0x07, 0xc0, 0x01, 0x44, 0x7f, 0xc8, 0x2f, 0xea, 0x43, 0x7c, 0xd1, 0x1e,
+        0xd2, 0x8b, 0xf2, 0x10, 0x5e, 0x75, 0x87, 0x84, 0xa5, 0x78, 0x01,
+    ];
+    assert_eq!(decompress(&dynamic).unwrap(), bottles());
+}
+
+#[test]
+fn test_inflate_errors() {
+    let compressed = compress(&bottles(), 9);
+    assert_eq!(decompress(&compressed[..compressed.len() / 2]), Err(InflateError::Truncated));
+    assert_eq!(decompress(&[0x07]), Err(InflateError::BadBlockType));
+    assert_eq!(decompress(&[0x01, 0x05, 0x00, 0x00, 0x00]), Err(InflateError::BadStoredLength));
+    // A fixed block starting with a match of distance 1 into empty output.
+    assert_eq!(decompress(&[0x03, 0x02, 0x00]), Err(InflateError::BadDistance));
+}
+
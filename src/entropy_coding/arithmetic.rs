@@ -0,0 +1,157 @@
+use bitio::{BitWriter, BitReader};
+use huffman_coding::{Freq, HuffmanError, byte_frequencies};
+
+use super::{normalize_frequencies, counts_from_table, table_from_counts, output_capacity};
+
+const FREQ_BITS: u32 = 16;
+const TOP: u64 = 0xffff_ffff;
+const HALF: u64 = 1 << 31;
+const QUARTER: u64 = 1 << 30;
+
+// Cumulative frequency model: symbol `s` owns `[cum[s], cum[s + 1])` out of
+// `1 << FREQ_BITS`.
+struct Model {
+    cum: Vec<u64>,
+}
+
+impl Model {
+    fn new(counts: &[usize]) -> Model {
+        let freqs = normalize_frequencies(counts, FREQ_BITS);
+        let mut cum = vec![0u64; 257];
+        for (s, &f) in freqs.iter().enumerate() {
+            cum[s + 1] = cum[s] + f as u64;
+        }
+        Model { cum }
+    }
+
+    fn total(&self) -> u64 {
+        self.cum[256]
+    }
+
+    fn find(&self, target: u64) -> usize {
+        // The last symbol whose interval starts at or below `target`.
+        match self.cum.binary_search(&target) {
+            Ok(mut s) => {
+                while self.cum[s + 1] == target {
+                    s += 1;
+                }
+                s
+            }
+            Err(s) => s - 1,
+        }
+    }
+}
+
+/// Binary arithmetic coder (Witten, Neal and Cleary) over 32-bit intervals
+/// with a static byte model.
+pub struct ArithmeticCoder {
+    counts: Vec<usize>,
+}
+
+impl ArithmeticCoder {
+    pub fn new() -> ArithmeticCoder {
+        ArithmeticCoder { counts: vec![0; 256] }
+    }
+
+    pub fn frequency_tbl(&self) -> Vec<Freq> {
+        table_from_counts(&self.counts)
+    }
+
+    pub fn compress_data(&mut self, data: Vec<u8>) -> Vec<u8> {
+        self.counts = byte_frequencies(&data);
+        let model = Model::new(&self.counts);
+
+        let mut writer = BitWriter::new();
+        let mut pending = 0;
+        let (mut low, mut high) = (0u64, TOP);
+        let emit = |writer: &mut BitWriter, bit: bool, pending: &mut u64| {
+            writer.write_bit(bit);
+            for _ in 0..*pending {
+                writer.write_bit(!bit);
+            }
+            *pending = 0;
+        };
+
+        for &byte in &data {
+            let s = byte as usize;
+            let range = high - low + 1;
+            high = low + range * model.cum[s + 1] / model.total() - 1;
+            low += range * model.cum[s] / model.total();
+            loop {
+                if high < HALF {
+                    emit(&mut writer, false, &mut pending);
+                } else if low >= HALF {
+                    emit(&mut writer, true, &mut pending);
+                    low -= HALF;
+                    high -= HALF;
+                } else if low >= QUARTER && high < HALF + QUARTER {
+                    pending += 1;
+                    low -= QUARTER;
+                    high -= QUARTER;
+                } else {
+                    break;
+                }
+                low <<= 1;
+                high = (high << 1) | 1;
+            }
+        }
+
+        // Two more bits pin down a value inside the final interval. The
+        // decoder looks 30 bits further ahead than that, so those are written
+        // out too and it never has to read past the end.
+        pending += 1;
+        emit(&mut writer, low >= QUARTER, &mut pending);
+        writer.write_bits(0, 30);
+        writer.into_bytes()
+    }
+
+    /// Decodes the output of `compress_data`; input that ends before
+    /// `count` symbols are decoded is reported as truncated.
+    pub fn decompress_data(&mut self, data: Vec<u8>, frequency_tbl: &[Freq])
+                           -> Result<Vec<u8>, HuffmanError> {
+        let (counts, count) = counts_from_table(frequency_tbl)?;
+        self.counts = counts;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let model = Model::new(&self.counts);
+
+        let mut reader = BitReader::new(&data);
+        let mut next_bit = || -> Result<u64, HuffmanError> { Ok(reader.read_bit()? as u64) };
+        let mut value = 0u64;
+        for _ in 0..32 {
+            value = (value << 1) | next_bit()?;
+        }
+
+        let (mut low, mut high) = (0u64, TOP);
+        let mut out = Vec::with_capacity(output_capacity(count, data.len()));
+        for _ in 0..count {
+            let range = high - low + 1;
+            let target = ((value - low + 1) * model.total() - 1) / range;
+            let s = model.find(target);
+            out.push(s as u8);
+
+            high = low + range * model.cum[s + 1] / model.total() - 1;
+            low += range * model.cum[s] / model.total();
+            loop {
+                if high < HALF {
+                    // Nothing to subtract.
+                } else if low >= HALF {
+                    low -= HALF;
+                    high -= HALF;
+                    value -= HALF;
+                } else if low >= QUARTER && high < HALF + QUARTER {
+                    low -= QUARTER;
+                    high -= QUARTER;
+                    value -= QUARTER;
+                } else {
+                    break;
+                }
+                low <<= 1;
+                high = (high << 1) | 1;
+                value = (value << 1) | next_bit()?;
+            }
+        }
+        Ok(out)
+    }
+}
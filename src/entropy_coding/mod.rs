@@ -0,0 +1,79 @@
+// Entropy coders to compare against `huffman_coding`. Both count symbols with
+// `huffman_coding::byte_frequencies`, hand the counts back as a `Freq` table
+// and need that table again to decompress, exactly like `Huffman`.
+
+use huffman_coding::{Freq, HuffmanError};
+
+mod arithmetic;
+mod rans;
+
+pub use self::arithmetic::ArithmeticCoder;
+pub use self::rans::RansCoder;
+
+/// Scales byte counts so they sum to exactly `1 << bits`, keeping every
+/// symbol that occurs at a frequency of at least one.
+pub fn normalize_frequencies(counts: &[usize], bits: u32) -> Vec<u32> {
+    let target = 1u64 << bits;
+    let total: u64 = counts.iter().map(|&c| c as u64).sum();
+    let mut freqs = vec![0u32; counts.len()];
+    if total == 0 {
+        return freqs;
+    }
+    assert!(counts.iter().filter(|&&c| c > 0).count() as u64 <= target,
+            "too many symbols for {}-bit frequencies", bits);
+
+    for (freq, &count) in freqs.iter_mut().zip(counts) {
+        if count > 0 {
+            *freq = ((count as u128 * target as u128 / total as u128) as u32).max(1);
+        }
+    }
+
+    // Rounding leaves the sum a little off; settle the difference on the
+    // most frequent symbols, where it costs the least.
+    let mut sum: u64 = freqs.iter().map(|&f| f as u64).sum();
+    while sum != target {
+        let (largest, _) = freqs.iter().enumerate().max_by_key(|&(_, &f)| f).unwrap();
+        if sum < target {
+            freqs[largest] += (target - sum) as u32;
+            sum = target;
+        } else {
+            let cut = (sum - target).min(freqs[largest] as u64 - 1);
+            freqs[largest] -= cut as u32;
+            sum -= cut;
+        }
+    }
+    freqs
+}
+
+/// Rebuilds the per-byte counts from a `Freq` table along with their total,
+/// the number of bytes to decode. Like `Huffman`, a table with a repeated
+/// byte, a zero count or counts that overflow is a bad table.
+fn counts_from_table(frequency_tbl: &[Freq]) -> Result<(Vec<usize>, usize), HuffmanError> {
+    let mut counts = vec![0usize; 256];
+    let mut total = 0usize;
+    for freq in frequency_tbl {
+        if counts[freq.byte as usize] > 0 || freq.count == 0 {
+            return Err(HuffmanError::BadTable);
+        }
+        counts[freq.byte as usize] = freq.count;
+        total = total.checked_add(freq.count).ok_or(HuffmanError::BadTable)?;
+    }
+    Ok((counts, total))
+}
+
+// The output length comes from the caller's table, so only reserve what the
+// input could reasonably expand to and let the vector grow past that.
+fn output_capacity(count: usize, input_len: usize) -> usize {
+    count.min(input_len.saturating_mul(8))
+}
+
+fn table_from_counts(counts: &[usize]) -> Vec<Freq> {
+    counts.iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .map(|(byte, &count)| Freq { byte: byte as u8, count })
+        .collect()
+}
+
+#[cfg(test)]
+mod test;
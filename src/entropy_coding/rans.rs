@@ -0,0 +1,104 @@
+use huffman_coding::{Freq, HuffmanError, byte_frequencies};
+
+use super::{normalize_frequencies, counts_from_table, table_from_counts, output_capacity};
+
+const SCALE_BITS: u32 = 12;
+const RANS_L: u32 = 1 << 23;
+
+struct Model {
+    freq: Vec<u32>,
+    start: Vec<u32>,
+    // Maps each of the `1 << SCALE_BITS` slots back to its symbol.
+    slots: Vec<u8>,
+}
+
+impl Model {
+    fn new(counts: &[usize]) -> Model {
+        let freq = normalize_frequencies(counts, SCALE_BITS);
+        let mut start = vec![0u32; 256];
+        let mut slots = Vec::with_capacity(1 << SCALE_BITS);
+        for s in 0..256 {
+            start[s] = slots.len() as u32;
+            for _ in 0..freq[s] {
+                slots.push(s as u8);
+            }
+        }
+        Model { freq, start, slots }
+    }
+}
+
+/// Byte-oriented range asymmetric numeral systems coder with a 32-bit state
+/// and a static byte model. rANS works like a stack, so the encoder processes
+/// the input backwards and the decoder reads the result forwards.
+pub struct RansCoder {
+    counts: Vec<usize>,
+}
+
+impl RansCoder {
+    pub fn new() -> RansCoder {
+        RansCoder { counts: vec![0; 256] }
+    }
+
+    pub fn frequency_tbl(&self) -> Vec<Freq> {
+        table_from_counts(&self.counts)
+    }
+
+    pub fn compress_data(&mut self, data: Vec<u8>) -> Vec<u8> {
+        self.counts = byte_frequencies(&data);
+        let model = Model::new(&self.counts);
+
+        let mut out = Vec::with_capacity(data.len() / 2 + 4);
+        let mut state = RANS_L;
+        for &byte in data.iter().rev() {
+            let freq = model.freq[byte as usize];
+            let x_max = ((RANS_L >> SCALE_BITS) << 8) * freq;
+            while state >= x_max {
+                out.push(state as u8);
+                state >>= 8;
+            }
+            state = ((state / freq) << SCALE_BITS) + state % freq + model.start[byte as usize];
+        }
+        for _ in 0..4 {
+            out.push(state as u8);
+            state >>= 8;
+        }
+        out.reverse();
+        out
+    }
+
+    /// Decodes the output of `compress_data`. The decoder consumes exactly
+    /// the bytes the encoder produced, so running out of input is an error.
+    pub fn decompress_data(&mut self, data: Vec<u8>, frequency_tbl: &[Freq])
+                           -> Result<Vec<u8>, HuffmanError> {
+        let (counts, count) = counts_from_table(frequency_tbl)?;
+        self.counts = counts;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let model = Model::new(&self.counts);
+
+        let mut bytes = data.iter();
+        let mut next_byte = || bytes.next().map(|&b| b as u32).ok_or(HuffmanError::Truncated);
+        let mut state = 0u32;
+        for _ in 0..4 {
+            state = (state << 8) | next_byte()?;
+        }
+        // The encoder always finishes with a state in `[RANS_L, RANS_L << 8)`.
+        if !(RANS_L..RANS_L << 8).contains(&state) {
+            return Err(HuffmanError::BadTable);
+        }
+
+        let mask = (1 << SCALE_BITS) - 1;
+        let mut out = Vec::with_capacity(output_capacity(count, data.len()));
+        for _ in 0..count {
+            let byte = model.slots[(state & mask) as usize];
+            out.push(byte);
+            let s = byte as usize;
+            state = model.freq[s] * (state >> SCALE_BITS) + (state & mask) - model.start[s];
+            while state < RANS_L {
+                state = (state << 8) | next_byte()?;
+            }
+        }
+        Ok(out)
+    }
+}
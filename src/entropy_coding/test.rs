@@ -0,0 +1,137 @@
+use huffman_coding::{Freq, Huffman, HuffmanError};
+
+use super::{ArithmeticCoder, RansCoder, normalize_frequencies};
+
+fn samples() -> Vec<Vec<u8>> {
+    let mut skewed: Vec<u8> = (0..5000).map(|i| if i % 50 == 0 { b'b' } else { b'a' }).collect();
+    skewed.extend_from_slice(b"xyz");
+    vec![
+        b"a".to_vec(),
+        b"so much words wow many compression".to_vec(),
+        b"abracadabra".repeat(100),
+        (0..=255u8).cycle().take(3000).collect(),
+        skewed,
+    ]
+}
+
+#[test]
+fn test_normalize_frequencies() {
+    let freqs = normalize_frequencies(&[1, 0, 1000000, 3], 12);
+    assert_eq!(freqs.iter().sum::<u32>(), 4096);
+    assert_eq!(freqs[0], 1);
+    assert_eq!(freqs[1], 0);
+    assert_eq!(freqs[3], 1);
+
+    let freqs = normalize_frequencies(&[1; 256], 8);
+    assert!(freqs.iter().all(|&f| f == 1));
+    assert_eq!(normalize_frequencies(&[0, 0], 8), vec![0, 0]);
+}
+
+#[test]
+fn test_arithmetic_round_trip() {
+    for data in samples() {
+        let mut coder = ArithmeticCoder::new();
+        let compressed = coder.compress_data(data.clone());
+        let table = coder.frequency_tbl();
+
+        let decompressed = ArithmeticCoder::new().decompress_data(compressed, &table).unwrap();
+        assert_eq!(decompressed, data);
+    }
+    assert_eq!(ArithmeticCoder::new().decompress_data(Vec::new(), &[]), Ok(Vec::new()));
+}
+
+#[test]
+fn test_rans_round_trip() {
+    for data in samples() {
+        let mut coder = RansCoder::new();
+        let compressed = coder.compress_data(data.clone());
+        let table = coder.frequency_tbl();
+
+        let decompressed = RansCoder::new().decompress_data(compressed, &table).unwrap();
+        assert_eq!(decompressed, data);
+    }
+    assert_eq!(RansCoder::new().decompress_data(Vec::new(), &[]), Ok(Vec::new()));
+}
+
+#[test]
+fn test_beats_huffman_on_skewed_data() {
+    // Huffman needs a whole bit for the dominant symbol; the other coders
+    // spend close to its entropy.
+    let data: Vec<u8> = (0..20000).map(|i| if i % 50 == 0 { b'b' } else { b'a' }).collect();
+
+    let huffman = Huffman::new().compress_data(data.clone()).len();
+    let arithmetic = ArithmeticCoder::new().compress_data(data.clone()).len();
+    let rans = RansCoder::new().compress_data(data).len();
+
+    assert!(arithmetic * 4 < huffman);
+    assert!(rans * 4 < huffman);
+}
+
+// Cuts near either end of `len` bytes of input, always including empty input.
+fn cuts(len: usize) -> Vec<usize> {
+    (0..len).filter(|&cut| cut < 16 || len - cut <= 16).collect()
+}
+
+#[test]
+fn test_empty_and_truncated_input() {
+    for data in samples() {
+        let mut arithmetic = ArithmeticCoder::new();
+        let compressed = arithmetic.compress_data(data.clone());
+        let table = arithmetic.frequency_tbl();
+        for len in cuts(compressed.len()) {
+            assert_eq!(ArithmeticCoder::new().decompress_data(compressed[..len].to_vec(), &table),
+                       Err(HuffmanError::Truncated));
+        }
+
+        let mut rans = RansCoder::new();
+        let compressed = rans.compress_data(data.clone());
+        let table = rans.frequency_tbl();
+        for len in cuts(compressed.len()) {
+            assert_eq!(RansCoder::new().decompress_data(compressed[..len].to_vec(), &table),
+                       Err(HuffmanError::Truncated));
+        }
+    }
+}
+
+#[test]
+fn test_bad_table() {
+    let huge = [Freq { byte: 0, count: usize::MAX }, Freq { byte: 1, count: 1 }];
+    assert_eq!(ArithmeticCoder::new().decompress_data(vec![0; 8], &huge), Err(HuffmanError::BadTable));
+    assert_eq!(RansCoder::new().decompress_data(vec![0; 8], &huge), Err(HuffmanError::BadTable));
+
+    // A state the encoder can never finish in.
+    let table = [Freq { byte: b'a', count: 3 }, Freq { byte: b'b', count: 1 }];
+    assert_eq!(RansCoder::new().decompress_data(vec![0xff; 8], &table), Err(HuffmanError::BadTable));
+}
+
+#[test]
+fn test_duplicate_symbol() {
+    let data = b"abracadabra".to_vec();
+    let mut coder = ArithmeticCoder::new();
+    let compressed = coder.compress_data(data.clone());
+    let mut table = coder.frequency_tbl();
+    table.push(table[0]);
+    assert_eq!(ArithmeticCoder::new().decompress_data(compressed, &table), Err(HuffmanError::BadTable));
+
+    let mut coder = RansCoder::new();
+    let compressed = coder.compress_data(data);
+    let mut table = coder.frequency_tbl();
+    table.push(table[0]);
+    assert_eq!(RansCoder::new().decompress_data(compressed, &table), Err(HuffmanError::BadTable));
+}
+
+#[test]
+fn test_zero_count() {
+    let data = b"abracadabra".to_vec();
+    let mut coder = ArithmeticCoder::new();
+    let compressed = coder.compress_data(data.clone());
+    let mut table = coder.frequency_tbl();
+    table.push(Freq { byte: b'z', count: 0 });
+    assert_eq!(ArithmeticCoder::new().decompress_data(compressed, &table), Err(HuffmanError::BadTable));
+
+    let mut coder = RansCoder::new();
+    let compressed = coder.compress_data(data);
+    let mut table = coder.frequency_tbl();
+    table.push(Freq { byte: b'z', count: 0 });
+    assert_eq!(RansCoder::new().decompress_data(compressed, &table), Err(HuffmanError::BadTable));
+}
@@ -0,0 +1,218 @@
+use segtree::{Monoid, ValueOp};
+
+/// A commutative `Monoid` where every element has an inverse, so ranges
+/// can be answered as the difference of two prefixes.
+pub trait Group: Monoid {
+    fn inverse(self) -> Self;
+}
+
+// `value` combined with itself `times` times, by doubling.
+fn scale<T: Group + Copy>(value: T, mut times: usize) -> T {
+    let (mut acc, mut base) = (T::identity(), value);
+    while times > 0 {
+        if times & 1 == 1 {
+            acc = ValueOp::op(acc, base);
+        }
+        base = ValueOp::op(base, base);
+        times >>= 1;
+    }
+    acc
+}
+
+/// Binary indexed tree: point updates and range queries in O(log n) with
+/// a single array of n + 1 partial sums.
+pub struct Fenwick<T> {
+    // 1-based; `tree[i]` covers the `i & i.wrapping_neg()` elements ending at i.
+    tree: Vec<T>,
+}
+
+impl<T: Group + Copy> Fenwick<T> {
+    /// `len` elements, all equal to the identity.
+    pub fn with_len(len: usize) -> Fenwick<T> {
+        Fenwick {
+            tree: vec![T::identity(); len + 1],
+        }
+    }
+
+    /// Builds the tree in O(n).
+    pub fn new(array: &[T]) -> Fenwick<T> {
+        let mut tree = vec![T::identity(); array.len() + 1];
+        tree[1..].copy_from_slice(array);
+        for i in 1..tree.len() {
+            let parent = i + (i & i.wrapping_neg());
+            if parent < tree.len() {
+                tree[parent] = ValueOp::op(tree[parent], tree[i]);
+            }
+        }
+        Fenwick { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Combines `delta` into the element at `index`.
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len(), "invalid index value");
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] = ValueOp::op(self.tree[i], delta);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Combination of the first `count` elements.
+    pub fn prefix(&self, count: usize) -> T {
+        assert!(count <= self.len(), "invalid prefix length");
+        let mut acc = T::identity();
+        let mut i = count;
+        while i > 0 {
+            acc = ValueOp::op(acc, self.tree[i]);
+            i -= i & i.wrapping_neg();
+        }
+        acc
+    }
+
+    /// Combines the elements in `[left, right]`.
+    pub fn query(&self, left: usize, right: usize) -> T {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len(), "invalid right bound");
+        ValueOp::op(self.prefix(right + 1), self.prefix(left).inverse())
+    }
+
+    pub fn replace(&mut self, index: usize, item: T) {
+        let old = self.query(index, index);
+        self.add(index, ValueOp::op(item, old.inverse()));
+    }
+}
+
+/// Fenwick tree taking range updates: `update` adds a delta to every
+/// element of a range and `query` still answers range sums, both in
+/// O(log n). Uses the two-tree trick where a prefix is `b1 * count - b2`.
+pub struct RangeFenwick<T> {
+    base: Fenwick<T>,
+    b1: Fenwick<T>,
+    b2: Fenwick<T>,
+}
+
+impl<T: Group + Copy> RangeFenwick<T> {
+    pub fn new(array: &[T]) -> RangeFenwick<T> {
+        // One spare slot so updates ending at the last element have
+        // somewhere to cancel.
+        RangeFenwick {
+            base: Fenwick::new(array),
+            b1: Fenwick::with_len(array.len() + 1),
+            b2: Fenwick::with_len(array.len() + 1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.base.is_empty()
+    }
+
+    /// Adds `delta` to every element in `[left, right]`.
+    pub fn update(&mut self, left: usize, right: usize, delta: T) {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len(), "invalid right bound");
+        self.b1.add(left, delta);
+        self.b1.add(right + 1, delta.inverse());
+        self.b2.add(left, scale(delta, left));
+        self.b2.add(right + 1, scale(delta, right + 1).inverse());
+    }
+
+    /// Combination of the first `count` elements.
+    pub fn prefix(&self, count: usize) -> T {
+        let added = ValueOp::op(scale(self.b1.prefix(count), count), self.b2.prefix(count).inverse());
+        ValueOp::op(self.base.prefix(count), added)
+    }
+
+    /// Combines the elements in `[left, right]`.
+    pub fn query(&self, left: usize, right: usize) -> T {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len(), "invalid right bound");
+        ValueOp::op(self.prefix(right + 1), self.prefix(left).inverse())
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        self.query(index, index)
+    }
+}
+
+/// Two-dimensional Fenwick tree over a `rows` x `cols` grid: point updates
+/// and rectangle sums in O(log rows * log cols).
+pub struct Fenwick2D<T> {
+    tree: Vec<Vec<T>>,
+    rows: usize,
+    cols: usize,
+}
+
+impl<T: Group + Copy> Fenwick2D<T> {
+    pub fn new(rows: usize, cols: usize) -> Fenwick2D<T> {
+        Fenwick2D {
+            tree: vec![vec![T::identity(); cols + 1]; rows + 1],
+            rows,
+            cols,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Combines `delta` into the cell at (`row`, `col`).
+    pub fn add(&mut self, row: usize, col: usize, delta: T) {
+        assert!(row < self.rows && col < self.cols, "invalid cell");
+        let mut i = row + 1;
+        while i <= self.rows {
+            let mut j = col + 1;
+            while j <= self.cols {
+                self.tree[i][j] = ValueOp::op(self.tree[i][j], delta);
+                j += j & j.wrapping_neg();
+            }
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Combination of the cells in the first `rows` rows and `cols` columns.
+    pub fn prefix(&self, rows: usize, cols: usize) -> T {
+        assert!(rows <= self.rows && cols <= self.cols, "invalid prefix size");
+        let mut acc = T::identity();
+        let mut i = rows;
+        while i > 0 {
+            let mut j = cols;
+            while j > 0 {
+                acc = ValueOp::op(acc, self.tree[i][j]);
+                j -= j & j.wrapping_neg();
+            }
+            i -= i & i.wrapping_neg();
+        }
+        acc
+    }
+
+    /// Combines the cells of the rectangle with corners (`top`, `left`) and
+    /// (`bottom`, `right`), both inclusive.
+    pub fn query(&self, top: usize, left: usize, bottom: usize, right: usize) -> T {
+        assert!(top <= bottom && left <= right, "empty rectangle");
+        assert!(bottom < self.rows && right < self.cols, "invalid rectangle");
+        let whole = self.prefix(bottom + 1, right + 1);
+        let above = self.prefix(top, right + 1);
+        let beside = self.prefix(bottom + 1, left);
+        let corner = self.prefix(top, left);
+        ValueOp::op(ValueOp::op(whole, corner), ValueOp::op(above, beside).inverse())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,111 @@
+use super::{Fenwick, RangeFenwick, Fenwick2D, Group};
+use rng::XorShift64;
+use segtree::{Monoid, ValueOp};
+use test::Bencher;
+
+impl ValueOp for i64 {
+    fn op(self, rhs: i64) -> i64 {
+        self + rhs
+    }
+}
+
+impl Monoid for i64 {
+    fn identity() -> i64 {
+        0
+    }
+}
+
+impl Group for i64 {
+    fn inverse(self) -> i64 {
+        -self
+    }
+}
+
+#[test]
+fn test_fenwick() {
+    let mut tree: Fenwick<i64> = Fenwick::new(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(tree.len(), 8);
+    assert_eq!(tree.query(0, 7), 36);
+    assert_eq!(tree.query(2, 5), 18);
+    assert_eq!(tree.query(1, 1), 2);
+    assert_eq!(tree.prefix(0), 0);
+    assert_eq!(tree.prefix(3), 6);
+    tree.replace(2, 10);
+    assert_eq!(tree.query(0, 4), 22);
+    tree.add(0, -6);
+    assert_eq!(tree.query(0, 0), -5);
+
+    let empty: Fenwick<i64> = Fenwick::new(&[]);
+    assert!(empty.is_empty());
+    assert_eq!(empty.prefix(0), 0);
+}
+
+#[test]
+fn test_fenwick_random() {
+    let mut rng = XorShift64::new(5);
+    let mut expected: Vec<i64> = (0..200).map(|_| rng.next_below(100) as i64).collect();
+    let mut tree = Fenwick::new(&expected);
+    let mut ranged = RangeFenwick::new(&expected);
+    let mut ranged_expected = expected.clone();
+    for _ in 0..2000 {
+        let (a, b) = (rng.next_below(200) as usize, rng.next_below(200) as usize);
+        let (left, right) = (a.min(b), a.max(b));
+        let delta = rng.next_below(200) as i64 - 100;
+        match rng.next_below(3) {
+            0 => {
+                tree.add(left, delta);
+                expected[left] += delta;
+            },
+            1 => {
+                ranged.update(left, right, delta);
+                for value in &mut ranged_expected[left..right + 1] {
+                    *value += delta;
+                }
+            },
+            _ => {
+                assert_eq!(tree.query(left, right), expected[left..right + 1].iter().sum::<i64>());
+                assert_eq!(ranged.query(left, right), ranged_expected[left..right + 1].iter().sum::<i64>());
+                assert_eq!(ranged.get(left), ranged_expected[left]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_fenwick_2d() {
+    let (rows, cols) = (13, 9);
+    let mut tree: Fenwick2D<i64> = Fenwick2D::new(rows, cols);
+    let mut grid = vec![vec![0i64; cols]; rows];
+    let mut rng = XorShift64::new(9);
+    for _ in 0..500 {
+        let (row, col) = (rng.next_below(rows as u64) as usize, rng.next_below(cols as u64) as usize);
+        let delta = rng.next_below(50) as i64 - 25;
+        tree.add(row, col, delta);
+        grid[row][col] += delta;
+
+        let (a, b) = (rng.next_below(rows as u64) as usize, rng.next_below(rows as u64) as usize);
+        let (c, d) = (rng.next_below(cols as u64) as usize, rng.next_below(cols as u64) as usize);
+        let (top, bottom, left, right) = (a.min(b), a.max(b), c.min(d), c.max(d));
+        let expected: i64 = grid[top..bottom + 1].iter().map(|row| row[left..right + 1].iter().sum::<i64>()).sum();
+        assert_eq!(tree.query(top, left, bottom, right), expected);
+    }
+    assert_eq!(tree.prefix(rows, cols), grid.iter().map(|row| row.iter().sum::<i64>()).sum::<i64>());
+}
+
+#[test]
+#[should_panic]
+fn test_fenwick_bad_range() {
+    let tree: Fenwick<i64> = Fenwick::new(&[1, 2, 3]);
+    tree.query(1, 3);
+}
+
+#[bench]
+fn bench_query_fenwick(b: &mut Bencher) {
+    b.iter(|| {
+        let array = vec![1i64; 100000];
+        let tree = Fenwick::new(&array);
+        for i in 0..100000 - 1000 {
+            assert_eq!(tree.query(i, i+1000), 1001);
+        }
+    });
+}
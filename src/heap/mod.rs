@@ -24,6 +24,10 @@ impl<T: PartialOrd> Heap<T> {
         self.inner.is_empty()
     }
 
+    pub fn top(&self) -> Option<&T> {
+        self.inner.first()
+    }
+
     fn shift_down(&mut self, idx: usize, heapsz: usize) {
         let mut parent_idx = idx;
         loop {
@@ -0,0 +1,292 @@
+// Adaptive Huffman coding (the FGK algorithm). Encoder and decoder start
+// from the same empty tree and update it identically after every symbol, so
+// no frequency table is ever transmitted and a single pass over the input
+// suffices.
+//
+// A symbol seen for the first time is sent as the code of the NYT ("not yet
+// transmitted") node followed by its 9-bit value. Value 256 never occurs in
+// the data and marks the end of the stream.
+
+use bitio::{BitWriter, BitReader};
+
+use super::HuffmanError;
+
+const EOF: u16 = 256;
+const SYMBOL_BITS: u32 = 9;
+
+#[derive(Clone, Copy)]
+struct Node {
+    weight: usize,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+    symbol: Option<u16>,
+}
+
+// Nodes are kept in implicit numbering order: a lower index means a higher
+// node number, the root is node 0 and weights never increase with the index
+// (the sibling property). New nodes are appended, so the NYT node is always
+// the last one.
+struct Tree {
+    nodes: Vec<Node>,
+    leaves: Vec<Option<usize>>,
+    nyt: usize,
+}
+
+impl Tree {
+    fn new() -> Tree {
+        Tree {
+            nodes: vec![Node { weight: 0, parent: None, left: None, right: None, symbol: None }],
+            leaves: vec![None; 257],
+            nyt: 0,
+        }
+    }
+
+    // Swaps the subtrees rooted at positions `a` and `b`, which have equal
+    // weights. Positions keep their parents; the contents move.
+    fn swap(&mut self, a: usize, b: usize) {
+        let (node_a, node_b) = (self.nodes[a], self.nodes[b]);
+        self.nodes[a] = Node { parent: node_a.parent, ..node_b };
+        self.nodes[b] = Node { parent: node_b.parent, ..node_a };
+        for &pos in &[a, b] {
+            let node = self.nodes[pos];
+            for child in node.left.iter().chain(node.right.iter()) {
+                self.nodes[*child].parent = Some(pos);
+            }
+            if let Some(symbol) = node.symbol {
+                self.leaves[symbol as usize] = Some(pos);
+            }
+        }
+        if self.nyt == a {
+            self.nyt = b;
+        } else if self.nyt == b {
+            self.nyt = a;
+        }
+    }
+
+    // Splits the NYT node into a new NYT node and a leaf for `symbol`,
+    // returning the leaf.
+    fn add_symbol(&mut self, symbol: u16) -> usize {
+        let parent = self.nyt;
+        let leaf = self.nodes.len();
+        let nyt = leaf + 1;
+        self.nodes.push(Node { weight: 0, parent: Some(parent), left: None, right: None,
+                               symbol: Some(symbol) });
+        self.nodes.push(Node { weight: 0, parent: Some(parent), left: None, right: None,
+                               symbol: None });
+        self.nodes[parent].left = Some(nyt);
+        self.nodes[parent].right = Some(leaf);
+        self.leaves[symbol as usize] = Some(leaf);
+        self.nyt = nyt;
+        leaf
+    }
+
+    fn update(&mut self, symbol: u16) {
+        let mut node = match self.leaves[symbol as usize] {
+            Some(leaf) => leaf,
+            None => self.add_symbol(symbol),
+        };
+        loop {
+            // The block leader is the highest-numbered node of equal weight.
+            let weight = self.nodes[node].weight;
+            let mut leader = node;
+            while leader > 0 && self.nodes[leader - 1].weight == weight {
+                leader -= 1;
+            }
+            if leader != node && Some(leader) != self.nodes[node].parent {
+                self.swap(leader, node);
+                node = leader;
+            }
+            self.nodes[node].weight += 1;
+            match self.nodes[node].parent {
+                Some(parent) => node = parent,
+                None => break,
+            }
+        }
+    }
+
+    fn write_path(&self, writer: &mut BitWriter, node: usize) {
+        let mut bits = Vec::new();
+        let mut child = node;
+        while let Some(parent) = self.nodes[child].parent {
+            bits.push(self.nodes[parent].right == Some(child));
+            child = parent;
+        }
+        for &bit in bits.iter().rev() {
+            writer.write_bit(bit);
+        }
+    }
+}
+
+pub struct AdaptiveEncoder {
+    tree: Tree,
+    writer: BitWriter,
+}
+
+impl AdaptiveEncoder {
+    pub fn new() -> AdaptiveEncoder {
+        AdaptiveEncoder {
+            tree: Tree::new(),
+            writer: BitWriter::new(),
+        }
+    }
+
+    fn encode_symbol(&mut self, symbol: u16) {
+        match self.tree.leaves[symbol as usize] {
+            Some(leaf) => self.tree.write_path(&mut self.writer, leaf),
+            None => {
+                self.tree.write_path(&mut self.writer, self.tree.nyt);
+                self.writer.write_bits(symbol as u64, SYMBOL_BITS);
+            }
+        }
+    }
+
+    pub fn encode(&mut self, byte: u8) {
+        self.encode_symbol(byte as u16);
+        self.tree.update(byte as u16);
+    }
+
+    /// Drains the completely written bytes, so output can be emitted while
+    /// encoding is still in progress.
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        self.writer.data()
+    }
+
+    /// Writes the end-of-stream marker and returns the remaining bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.encode_symbol(EOF);
+        self.writer.into_bytes()
+    }
+}
+
+pub struct AdaptiveDecoder<'a> {
+    tree: Tree,
+    reader: BitReader<'a>,
+    done: bool,
+}
+
+impl<'a> AdaptiveDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> AdaptiveDecoder<'a> {
+        AdaptiveDecoder {
+            tree: Tree::new(),
+            reader: BitReader::new(data),
+            done: false,
+        }
+    }
+
+    /// Decodes the next byte, or `None` once the end-of-stream marker has
+    /// been read.
+    pub fn decode(&mut self) -> Result<Option<u8>, HuffmanError> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut node = 0;
+        while let (Some(left), Some(right)) = (self.tree.nodes[node].left,
+                                               self.tree.nodes[node].right) {
+            node = if self.reader.read_bit()? { right } else { left };
+        }
+        let symbol = match self.tree.nodes[node].symbol {
+            Some(symbol) => symbol,
+            None => {
+                let symbol = self.reader.read_bits(SYMBOL_BITS)? as u16;
+                if symbol > EOF || (symbol < EOF && self.tree.leaves[symbol as usize].is_some()) {
+                    return Err(HuffmanError::BadTable);
+                }
+                symbol
+            }
+        };
+        if symbol == EOF {
+            self.done = true;
+            return Ok(None);
+        }
+        self.tree.update(symbol);
+        Ok(Some(symbol as u8))
+    }
+}
+
+pub struct AdaptiveHuffman;
+
+impl AdaptiveHuffman {
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = AdaptiveEncoder::new();
+        for &byte in data {
+            encoder.encode(byte);
+        }
+        encoder.finish()
+    }
+
+    pub fn decompress(data: &[u8]) -> Result<Vec<u8>, HuffmanError> {
+        let mut decoder = AdaptiveDecoder::new(data);
+        let mut out = Vec::new();
+        while let Some(byte) = decoder.decode()? {
+            out.push(byte);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_sibling_property(tree: &Tree) {
+        for i in 1..tree.nodes.len() {
+            assert!(tree.nodes[i - 1].weight >= tree.nodes[i].weight);
+        }
+        for node in &tree.nodes {
+            if let (Some(left), Some(right)) = (node.left, node.right) {
+                assert_eq!(node.weight, tree.nodes[left].weight + tree.nodes[right].weight);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sibling_property() {
+        let mut tree = Tree::new();
+        for &byte in b"abracadabra alakazam" {
+            tree.update(byte as u16);
+            check_sibling_property(&tree);
+        }
+        assert_eq!(tree.nodes[0].weight, 20);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for s in &["", "a", "abracadabra", "so much words wow many compression"] {
+            let compressed = AdaptiveHuffman::compress(s.as_bytes());
+            assert_eq!(AdaptiveHuffman::decompress(&compressed).unwrap(), s.as_bytes());
+        }
+
+        let all: Vec<u8> = (0..=255u8).chain((0..=255u8).rev()).cycle().take(10000).collect();
+        let compressed = AdaptiveHuffman::compress(&all);
+        assert_eq!(AdaptiveHuffman::decompress(&compressed).unwrap(), all);
+    }
+
+    #[test]
+    fn test_compresses_skewed_input() {
+        let data: Vec<u8> = (0..10000).map(|i| if i % 10 == 0 { b'b' } else { b'a' }).collect();
+        let compressed = AdaptiveHuffman::compress(&data);
+        assert!(compressed.len() < data.len() / 6);
+    }
+
+    #[test]
+    fn test_incremental_output() {
+        let data = b"the quick brown fox jumps over the lazy dog".repeat(20);
+        let mut encoder = AdaptiveEncoder::new();
+        let mut out = Vec::new();
+        for &byte in &data {
+            encoder.encode(byte);
+            out.extend(encoder.take_bytes());
+        }
+        assert!(!out.is_empty());
+        out.extend(encoder.finish());
+        assert_eq!(AdaptiveHuffman::decompress(&out).unwrap(), data);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let compressed = AdaptiveHuffman::compress(b"abracadabra");
+        assert_eq!(AdaptiveHuffman::decompress(&compressed[..compressed.len() - 2]),
+                   Err(HuffmanError::Truncated));
+    }
+}
@@ -0,0 +1,389 @@
+// Canonical Huffman coding. Only the code length of every symbol has to be
+// transmitted: codes are assigned in order of (length, symbol) exactly as
+// DEFLATE does, so the decoder can rebuild them from the lengths alone.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use bitio::{BitWriter, BitReader};
+
+use super::{HuffmanError, byte_frequencies};
+
+/// Longest code the compact header can describe (and the DEFLATE limit).
+pub const MAX_CODE_LENGTH: u8 = 15;
+
+// One item of the package-merge lists: its total weight and the leaves it is
+// built from. Every appearance of a leaf in the selected items adds one to
+// that symbol's code length.
+#[derive(Clone)]
+struct Package {
+    weight: usize,
+    symbols: Vec<usize>,
+}
+
+fn merge(leaves: &[Package], packages: Vec<Package>) -> Vec<Package> {
+    let mut merged = Vec::with_capacity(leaves.len() + packages.len());
+    let mut leaves = leaves.iter().cloned().peekable();
+    let mut packages = packages.into_iter().peekable();
+    loop {
+        let take_leaf = match (leaves.peek(), packages.peek()) {
+            (Some(leaf), Some(package)) => leaf.weight <= package.weight,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        let next = if take_leaf { leaves.next() } else { packages.next() };
+        merged.extend(next);
+    }
+    merged
+}
+
+/// Optimal code lengths for `freqs` with no code longer than `max_len` bits,
+/// computed with the package-merge algorithm. Symbols with a zero frequency
+/// get length 0; a lone used symbol gets length 1.
+pub fn limited_code_lengths(freqs: &[usize], max_len: u8) -> Vec<u8> {
+    let mut lengths = vec![0u8; freqs.len()];
+    let mut leaves: Vec<Package> = freqs.iter()
+        .enumerate()
+        .filter(|&(_, &freq)| freq > 0)
+        .map(|(symbol, &freq)| Package { weight: freq, symbols: vec![symbol] })
+        .collect();
+    match leaves.len() {
+        0 => return lengths,
+        1 => {
+            lengths[leaves[0].symbols[0]] = 1;
+            return lengths;
+        }
+        n => assert!(max_len < 64 && n <= 1usize << max_len,
+                     "{} symbols do not fit in {}-bit codes", n, max_len),
+    }
+    leaves.sort_by_key(|leaf| leaf.weight);
+
+    let mut current = leaves.clone();
+    for _ in 1..max_len {
+        let packages = current.chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| {
+                let mut symbols = pair[0].symbols.clone();
+                symbols.extend_from_slice(&pair[1].symbols);
+                Package { weight: pair[0].weight + pair[1].weight, symbols }
+            })
+            .collect();
+        current = merge(&leaves, packages);
+    }
+
+    for item in current.iter().take(2 * leaves.len() - 2) {
+        for &symbol in &item.symbols {
+            lengths[symbol] += 1;
+        }
+    }
+    lengths
+}
+
+/// Unrestricted optimal code lengths, the same ones a Huffman tree yields.
+/// Symbols with a zero frequency get length 0; a lone used symbol gets
+/// length 1.
+pub fn code_lengths(freqs: &[usize]) -> Vec<u8> {
+    let mut lengths = vec![0u8; freqs.len()];
+    let leaves: Vec<usize> = (0..freqs.len()).filter(|&symbol| freqs[symbol] > 0).collect();
+    assert!(leaves.len() <= 256, "at most 256 symbols are supported");
+    match leaves.len() {
+        0 => return lengths,
+        1 => {
+            lengths[leaves[0]] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    // Nodes 0..leaves.len() are the leaves; every merge appends a parent.
+    // Ties are broken by node index, so the result is deterministic.
+    let mut parent = vec![0usize; 2 * leaves.len() - 1];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = leaves.iter()
+        .enumerate()
+        .map(|(node, &symbol)| Reverse((freqs[symbol], node)))
+        .collect();
+    let mut next = leaves.len();
+    while let (Some(Reverse((w1, n1))), Some(Reverse((w2, n2)))) = (heap.pop(), heap.pop()) {
+        parent[n1] = next;
+        parent[n2] = next;
+        heap.push(Reverse((w1.saturating_add(w2), next)));
+        next += 1;
+    }
+
+    // Parents always come after their children, so one backwards pass
+    // settles every depth; the root is the last node.
+    let mut depth = vec![0u8; parent.len()];
+    for node in (0..parent.len() - 1).rev() {
+        depth[node] = depth[parent[node]] + 1;
+    }
+    for (node, &symbol) in leaves.iter().enumerate() {
+        lengths[symbol] = depth[node];
+    }
+    lengths
+}
+
+/// Assigns canonical codes to `lengths`. Returns `None` if the lengths are
+/// over-subscribed, i.e. do not describe a prefix code.
+pub fn canonical_codes(lengths: &[u8]) -> Option<Vec<u32>> {
+    let max_len = lengths.iter().cloned().max().unwrap_or(0) as usize;
+    if max_len > 32 {
+        return None;
+    }
+    let mut bl_count = vec![0u64; max_len + 1];
+    for &len in lengths {
+        bl_count[len as usize] += 1;
+    }
+    bl_count[0] = 0;
+
+    let mut next_code = vec![0u64; max_len + 1];
+    let mut code = 0u64;
+    for len in 1..max_len + 1 {
+        code = (code + bl_count[len - 1]) << 1;
+        next_code[len] = code;
+        if code + bl_count[len] > 1u64 << len {
+            return None;
+        }
+    }
+
+    let codes = lengths.iter()
+        .map(|&len| if len == 0 {
+            0
+        } else {
+            let code = next_code[len as usize];
+            next_code[len as usize] += 1;
+            code as u32
+        })
+        .collect();
+    Some(codes)
+}
+
+pub struct CanonicalHuffman {
+    lengths: Vec<u8>,
+    codes: Vec<u32>,
+    // Decoding tables: how many codes have each length, and the symbols
+    // sorted by (length, symbol).
+    counts: Vec<usize>,
+    sorted: Vec<u8>,
+}
+
+impl CanonicalHuffman {
+    pub fn from_lengths(lengths: &[u8]) -> Result<CanonicalHuffman, HuffmanError> {
+        if lengths.len() > 256 {
+            return Err(HuffmanError::BadTable);
+        }
+        let codes = canonical_codes(lengths).ok_or(HuffmanError::BadTable)?;
+
+        let max_len = lengths.iter().cloned().max().unwrap_or(0) as usize;
+        let mut counts = vec![0; max_len + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut sorted: Vec<u8> = (0..lengths.len())
+            .filter(|&symbol| lengths[symbol] > 0)
+            .map(|symbol| symbol as u8)
+            .collect();
+        sorted.sort_by_key(|&symbol| lengths[symbol as usize]);
+
+        Ok(CanonicalHuffman {
+            lengths: lengths.to_vec(),
+            codes,
+            counts,
+            sorted,
+        })
+    }
+
+    pub fn from_frequencies(freqs: &[usize], max_len: u8) -> CanonicalHuffman {
+        CanonicalHuffman::from_lengths(&limited_code_lengths(freqs, max_len))
+            .expect("package-merge produced an invalid code")
+    }
+
+    pub fn lengths(&self) -> &[u8] {
+        &self.lengths
+    }
+
+    /// The code of `symbol` and its length in bits; length 0 means the
+    /// symbol has no code.
+    pub fn code(&self, symbol: u8) -> (u32, u8) {
+        match self.lengths.get(symbol as usize) {
+            Some(&len) => (self.codes[symbol as usize], len),
+            None => (0, 0),
+        }
+    }
+
+    fn encode_symbol(&self, writer: &mut BitWriter, symbol: u8) {
+        let (code, len) = self.code(symbol);
+        assert!(len > 0, "symbol {} has no code", symbol);
+        writer.write_bits(code as u64, len as u32);
+    }
+
+    // Canonical decoding without a tree: after reading `len` bits, the codes
+    // of that length form the contiguous range starting at `first`.
+    fn decode_symbol(&self, reader: &mut BitReader) -> Result<u8, HuffmanError> {
+        let mut code = 0usize;
+        let mut first = 0usize;
+        let mut index = 0usize;
+        for len in 1..self.counts.len() {
+            code |= reader.read_bit()? as usize;
+            let count = self.counts[len];
+            if code - first < count {
+                return Ok(self.sorted[index + code - first]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(HuffmanError::BadTable)
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        for &byte in data {
+            self.encode_symbol(&mut writer, byte);
+        }
+        writer.into_bytes()
+    }
+
+    pub fn decode(&self, bits: &[u8], count: usize) -> Result<Vec<u8>, HuffmanError> {
+        let mut reader = BitReader::new(bits);
+        (0..count).map(|_| self.decode_symbol(&mut reader)).collect()
+    }
+
+    /// Compresses `data` with codes capped at `MAX_CODE_LENGTH` bits. The
+    /// header is the original length (u64 LE) followed by the 256 code
+    /// lengths packed two per byte.
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let huffman = CanonicalHuffman::from_frequencies(&byte_frequencies(data), MAX_CODE_LENGTH);
+
+        let mut out = Vec::with_capacity(8 + 128 + data.len());
+        for i in 0..8 {
+            out.push((data.len() as u64 >> (8 * i)) as u8);
+        }
+        for pair in huffman.lengths.chunks(2) {
+            out.push((pair[0] << 4) | pair[1]);
+        }
+        out.extend(huffman.encode(data));
+        out
+    }
+
+    pub fn decompress(data: &[u8]) -> Result<Vec<u8>, HuffmanError> {
+        if data.len() < 8 + 128 {
+            return Err(HuffmanError::Truncated);
+        }
+        let count = data[..8].iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64) as usize;
+        let mut lengths = Vec::with_capacity(256);
+        for &byte in &data[8..8 + 128] {
+            lengths.push(byte >> 4);
+            lengths.push(byte & 0x0f);
+        }
+        if count > 0 && lengths.iter().all(|&len| len == 0) {
+            return Err(HuffmanError::BadTable);
+        }
+        CanonicalHuffman::from_lengths(&lengths)?.decode(&data[8 + 128..], count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kraft_sum(lengths: &[u8]) -> f64 {
+        lengths.iter()
+            .filter(|&&len| len > 0)
+            .map(|&len| 0.5f64.powi(len as i32))
+            .sum()
+    }
+
+    #[test]
+    fn test_canonical_codes() {
+        // The example from RFC 1951, section 3.2.2.
+        let lengths = [3, 3, 3, 3, 3, 2, 4, 4];
+        let codes = canonical_codes(&lengths).unwrap();
+        assert_eq!(codes, vec![0b010, 0b011, 0b100, 0b101, 0b110, 0b00, 0b1110, 0b1111]);
+
+        assert_eq!(canonical_codes(&[1, 1, 1]), None);
+    }
+
+    #[test]
+    fn test_code_lengths() {
+        let freqs = [5, 9, 12, 13, 16, 45];
+        let lengths = code_lengths(&freqs);
+        assert_eq!(lengths, vec![4, 4, 3, 3, 3, 1]);
+        assert_eq!(kraft_sum(&lengths), 1.0);
+    }
+
+    #[test]
+    fn test_code_lengths_all_symbols() {
+        for &used in &[65usize, 200, 256] {
+            let freqs: Vec<usize> = (0..256).map(|i| if i < used { 1 + i * i % 37 } else { 0 }).collect();
+            let lengths = code_lengths(&freqs);
+            assert_eq!(lengths.iter().filter(|&&len| len > 0).count(), used);
+            assert_eq!(kraft_sum(&lengths), 1.0);
+            assert!(canonical_codes(&lengths).is_some());
+        }
+
+        let flat = code_lengths(&[1; 256]);
+        assert!(flat.iter().all(|&len| len == 8));
+        let huge = code_lengths(&[usize::MAX, usize::MAX, 1]);
+        assert_eq!(kraft_sum(&huge), 1.0);
+    }
+
+    #[test]
+    fn test_limited_code_lengths() {
+        // Fibonacci weights produce the deepest possible tree.
+        let mut freqs = vec![1usize, 1];
+        while freqs.len() < 20 {
+            let n = freqs.len();
+            let next = freqs[n - 1] + freqs[n - 2];
+            freqs.push(next);
+        }
+        assert_eq!(code_lengths(&freqs).iter().cloned().max(), Some(19));
+
+        let lengths = limited_code_lengths(&freqs, 7);
+        assert_eq!(lengths.iter().cloned().max(), Some(7));
+        assert_eq!(kraft_sum(&lengths), 1.0);
+        // Heavier symbols never get longer codes than lighter ones.
+        for i in 1..lengths.len() {
+            assert!(lengths[i] <= lengths[i - 1]);
+        }
+    }
+
+    #[test]
+    fn test_degenerate_lengths() {
+        assert_eq!(limited_code_lengths(&[0, 0, 0], 15), vec![0, 0, 0]);
+        assert_eq!(limited_code_lengths(&[0, 7, 0], 15), vec![0, 1, 0]);
+        assert_eq!(limited_code_lengths(&[3, 3, 3, 3], 2), vec![2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let s = "so much words wow many compression";
+        let compressed = CanonicalHuffman::compress(s.as_bytes());
+        assert_eq!(CanonicalHuffman::decompress(&compressed).unwrap(), s.as_bytes());
+
+        let single = vec![b'z'; 100];
+        let compressed = CanonicalHuffman::compress(&single);
+        assert_eq!(compressed.len(), 8 + 128 + 13);
+        assert_eq!(CanonicalHuffman::decompress(&compressed).unwrap(), single);
+
+        let all: Vec<u8> = (0..=255u8).cycle().take(5000).collect();
+        let compressed = CanonicalHuffman::compress(&all);
+        assert_eq!(CanonicalHuffman::decompress(&compressed).unwrap(), all);
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        let compressed = CanonicalHuffman::compress(b"abracadabra");
+        assert_eq!(CanonicalHuffman::decompress(&compressed[..100]), Err(HuffmanError::Truncated));
+        assert_eq!(CanonicalHuffman::decompress(&compressed[..compressed.len() - 1]),
+                   Err(HuffmanError::Truncated));
+
+        let mut bad = compressed.clone();
+        // Give every symbol a 1-bit code, which cannot be a prefix code.
+        for byte in &mut bad[8..8 + 128] {
+            *byte = 0x11;
+        }
+        assert_eq!(CanonicalHuffman::decompress(&bad), Err(HuffmanError::BadTable));
+    }
+}
@@ -0,0 +1,171 @@
+// Self-describing container around the packed Huffman bits:
+//
+//   magic    4 bytes  b"RHUF"
+//   version  1 byte
+//   length   8 bytes  original data length, little endian
+//   symbols  2 bytes  number of frequency table entries, little endian
+//   table    9 bytes per entry: symbol byte, then its count as u64 LE
+//   payload  the packed code bits
+//   crc32    4 bytes  CRC-32 of the original data, little endian
+
+use super::{Huffman, Freq, HuffmanError};
+
+const MAGIC: &[u8; 4] = b"RHUF";
+const VERSION: u8 = 1;
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+
+    let mut crc = !0u32;
+    for &byte in data {
+        crc = table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+fn read_bytes<'a>(data: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], HuffmanError> {
+    if data.len() - *pos < n {
+        return Err(HuffmanError::Truncated);
+    }
+    let bytes = &data[*pos..*pos + n];
+    *pos += n;
+    Ok(bytes)
+}
+
+fn read_le(data: &[u8], pos: &mut usize, n: usize) -> Result<u64, HuffmanError> {
+    let bytes = read_bytes(data, pos, n)?;
+    Ok(bytes.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+}
+
+fn write_le(out: &mut Vec<u8>, value: u64, n: usize) {
+    for i in 0..n {
+        out.push((value >> (8 * i)) as u8);
+    }
+}
+
+impl Huffman {
+    /// Compresses `data` into a self-contained blob that
+    /// `decompress_from_slice` can read back without any side information.
+    pub fn compress_to_vec(data: &[u8]) -> Vec<u8> {
+        let mut huffman = Huffman::new();
+        let payload = huffman.compress_data(data.to_vec());
+        let table = huffman.frequency_tbl();
+
+        let mut out = Vec::with_capacity(15 + 9 * table.len() + payload.len() + 4);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        write_le(&mut out, data.len() as u64, 8);
+        write_le(&mut out, table.len() as u64, 2);
+        for freq in &table {
+            out.push(freq.byte);
+            write_le(&mut out, freq.count as u64, 8);
+        }
+        out.extend_from_slice(&payload);
+        write_le(&mut out, crc32(data) as u64, 4);
+        out
+    }
+
+    pub fn decompress_from_slice(data: &[u8]) -> Result<Vec<u8>, HuffmanError> {
+        let mut pos = 0;
+        if read_bytes(data, &mut pos, 4)? != MAGIC {
+            return Err(HuffmanError::BadMagic);
+        }
+        let version = read_bytes(data, &mut pos, 1)?[0];
+        if version != VERSION {
+            return Err(HuffmanError::UnsupportedVersion(version));
+        }
+        let length = read_le(data, &mut pos, 8)?;
+        let symbols = read_le(data, &mut pos, 2)? as usize;
+        if symbols > 256 {
+            return Err(HuffmanError::BadTable);
+        }
+
+        let mut seen = [false; 256];
+        let mut total = 0u64;
+        let mut table = Vec::with_capacity(symbols);
+        for _ in 0..symbols {
+            let byte = read_bytes(data, &mut pos, 1)?[0];
+            let count = read_le(data, &mut pos, 8)?;
+            if seen[byte as usize] || count == 0 {
+                return Err(HuffmanError::BadTable);
+            }
+            seen[byte as usize] = true;
+            total = total.checked_add(count).ok_or(HuffmanError::BadTable)?;
+            table.push(Freq { byte, count: count as usize });
+        }
+        if total != length {
+            return Err(HuffmanError::BadTable);
+        }
+
+        if data.len() - pos < 4 {
+            return Err(HuffmanError::Truncated);
+        }
+        let payload = &data[pos..data.len() - 4];
+        pos = data.len() - 4;
+        let checksum = read_le(data, &mut pos, 4)? as u32;
+
+        let decoded = Huffman::new().decompress_data(payload.to_vec(), &table)?;
+        if crc32(&decoded) != checksum {
+            return Err(HuffmanError::ChecksumMismatch);
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let s = "so much words wow many compression";
+        let blob = Huffman::compress_to_vec(s.as_bytes());
+
+        assert_eq!(&blob[..4], b"RHUF");
+        assert_eq!(Huffman::decompress_from_slice(&blob).unwrap(), s.as_bytes());
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let mut blob = Huffman::compress_to_vec(b"abracadabra");
+
+        assert_eq!(Huffman::decompress_from_slice(&blob[..3]), Err(HuffmanError::Truncated));
+        assert_eq!(Huffman::decompress_from_slice(&blob[..20]), Err(HuffmanError::Truncated));
+
+        blob[4] = 9;
+        assert_eq!(Huffman::decompress_from_slice(&blob),
+                   Err(HuffmanError::UnsupportedVersion(9)));
+        blob[0] = b'X';
+        assert_eq!(Huffman::decompress_from_slice(&blob), Err(HuffmanError::BadMagic));
+    }
+
+    #[test]
+    fn test_bad_table() {
+        let mut blob = Huffman::compress_to_vec(b"abracadabra");
+        // Bump the count of the first table entry so the sum no longer
+        // matches the stored length.
+        blob[16] += 1;
+        assert_eq!(Huffman::decompress_from_slice(&blob), Err(HuffmanError::BadTable));
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut blob = Huffman::compress_to_vec(b"abracadabra");
+        let len = blob.len();
+        blob[len - 1] ^= 0xff;
+        assert_eq!(Huffman::decompress_from_slice(&blob), Err(HuffmanError::ChecksumMismatch));
+    }
+}
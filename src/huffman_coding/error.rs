@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt;
+
+use bitio::BitIoError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HuffmanError {
+    /// The input does not start with the container magic bytes.
+    BadMagic,
+    /// The container was written by an unknown format version.
+    UnsupportedVersion(u8),
+    /// The input ended before all expected data was read.
+    Truncated,
+    /// The serialized code table is inconsistent.
+    BadTable,
+    /// The decoded data does not match the stored checksum.
+    ChecksumMismatch,
+    /// A variable-length code in the input does not fit in 64 bits.
+    CodeOverflow,
+}
+
+impl fmt::Display for HuffmanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HuffmanError::BadMagic => write!(f, "not a huffman container"),
+            HuffmanError::UnsupportedVersion(v) => write!(f, "unsupported container version {}", v),
+            HuffmanError::Truncated => write!(f, "compressed data is truncated"),
+            HuffmanError::BadTable => write!(f, "invalid code table"),
+            HuffmanError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            HuffmanError::CodeOverflow => write!(f, "variable-length code overflows 64 bits"),
+        }
+    }
+}
+
+impl Error for HuffmanError {}
+
+impl From<BitIoError> for HuffmanError {
+    fn from(err: BitIoError) -> HuffmanError {
+        match err {
+            BitIoError::UnexpectedEof => HuffmanError::Truncated,
+            BitIoError::Overflow => HuffmanError::CodeOverflow,
+        }
+    }
+}
@@ -2,92 +2,29 @@ use std::cmp::Ordering;
 use std::fmt;
 
 use super::priority_queue::PriorityQueue;
-
-struct BitWriter {
-    buf: Vec<u8>,
-    out_byte: u8,
-    out_count: usize,
-}
-
-impl fmt::Debug for BitWriter {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut byte = self.out_byte;
-        let mut ans: Vec<bool> = Vec::new();
-        while byte != 0 {
-            if byte & 0x80 != 0 {
-                ans.push(true);
-            } else {
-                ans.push(false);
-            }
-            byte <<= 1;
-        }
-        writeln!(f, "{:?}", ans)
-    }
-}
-
-impl BitWriter {
-    fn new() -> BitWriter {
-        BitWriter {
-            buf: Vec::new(),
-            out_byte: 0u8,
-            out_count: 0,
-        }
-    }
-
-    fn write_bit(&mut self, bit: bool) {
-        if self.out_count == 8 {
-            self.buf.push(self.out_byte);
-            self.out_byte = 0;
-            self.out_count = 0;
-        }
-        self.out_byte = (self.out_byte << 1) | (if bit { 1 } else { 0 });
-        self.out_count += 1;
-    }
-
-    fn flush(&mut self) {
-        if self.out_count > 0 {
-            if self.out_count < 8 {
-                let diff = 8 - self.out_count;
-                self.out_byte <<= diff;
-            }
-            self.buf.push(self.out_byte);
-        }
-    }
-
-    fn data(&mut self) -> Vec<u8> {
-        self.buf.drain(..).collect()
-    }
-}
-
-struct BitReader<'a> {
-    buf: &'a [u8],
-    in_byte: u8,
-    ptr: usize,
-    in_count: usize,
-}
-
-impl<'a> BitReader<'a> {
-    fn new(buf: &'a [u8]) -> BitReader {
-        BitReader {
-            buf: buf,
-            in_byte: 0,
-            in_count: 8,
-            ptr: 0,
-        }
-    }
-
-    fn read_bit(&mut self) -> bool {
-        if self.in_count == 8 {
-            self.in_byte = self.buf[self.ptr];
-            self.ptr += 1;
-            self.in_count = 0;
-        }
-        self.in_count += 1;
-
-        let ret = !((self.in_byte & 0x80) == 0);
-        self.in_byte <<= 1;
-        ret
+use super::bitio::{BitWriter, BitReader};
+
+mod error;
+mod container;
+mod canonical;
+mod table;
+mod stream;
+mod adaptive;
+
+pub use self::error::HuffmanError;
+pub use self::canonical::{CanonicalHuffman, MAX_CODE_LENGTH, code_lengths,
+                          limited_code_lengths, canonical_codes};
+pub use self::table::{EncodeTable, DecodeTable};
+pub use self::stream::{HuffmanEncoder, HuffmanDecoder, DEFAULT_BLOCK_SIZE, MAX_BLOCK_SIZE};
+pub use self::adaptive::{AdaptiveHuffman, AdaptiveEncoder, AdaptiveDecoder};
+
+/// Occurrence count of every byte value in `data`, indexed by byte.
+pub fn byte_frequencies(data: &[u8]) -> Vec<usize> {
+    let mut freqs = vec![0; 256];
+    for &byte in data {
+        freqs[byte as usize] += 1;
     }
+    freqs
 }
 
 #[derive(PartialEq, Clone, Copy)]
@@ -131,8 +68,8 @@ impl fmt::Debug for Node {
 
 #[derive(Clone, Copy)]
 pub struct Freq {
-    byte: u8,
-    count: usize,
+    pub byte: u8,
+    pub count: usize,
 }
 
 impl fmt::Debug for Freq {
@@ -170,13 +107,12 @@ impl fmt::Debug for Huffman {
 
 impl Huffman {
     fn count_byte_freq(&mut self, data: &[u8]) {
-        let _: Vec<_> = data.into_iter().map(|&byte| {
-            let idx = byte as usize;
-            self.tree[idx].count += 1;
-            self.tree[idx].index = idx as i32;
-        }).collect();
-
-        // self.build_tree();
+        for (idx, &count) in byte_frequencies(data).iter().enumerate() {
+            if count > 0 {
+                self.tree[idx].count += count;
+                self.tree[idx].index = idx as i32;
+            }
+        }
     }
 
     fn build_tree(&mut self) {
@@ -186,7 +122,15 @@ impl Huffman {
             .map(|&node| {
                 queue.enqueue(node)                    
             }).collect::<Vec<_>>();
-        
+
+        // A lone symbol would be the root itself and get an empty code. Pair
+        // it with an unused byte so it is coded with one bit instead.
+        if queue.count() == 1 {
+            let node = queue.peek().unwrap();
+            let dummy = (node.index as usize + 1) % 256;
+            self.tree[dummy].index = dummy as i32;
+            queue.enqueue(self.tree[dummy]);
+        }
 
         while queue.count() > 1 {
             let node1 = queue.dequeue().unwrap();
@@ -205,11 +149,14 @@ impl Huffman {
             queue.enqueue(parent_node);
         }
 
-        let root_node = queue.dequeue().unwrap();
-        self.root = root_node.index;
+        self.root = match queue.dequeue() {
+            Some(root_node) => root_node.index,
+            None => -1,
+        };
     }
 
     fn restore_tree(&mut self, frequency_tbl: &[Freq]) {
+        *self = Huffman::new();
         for freq in frequency_tbl.iter() {
             let byte = freq.byte;
             self.tree[byte as usize].index = byte as i32;
@@ -232,16 +179,32 @@ impl Huffman {
         }
     }
 
-    fn find_leaf_node(&self, reader: &mut BitReader, node_idx: i32) -> u8 {
+    fn find_leaf_node(&self, reader: &mut BitReader, node_idx: i32) -> Result<u8, HuffmanError> {
         let mut idx = node_idx as usize;
         while self.tree[idx].right != -1 {
-            if reader.read_bit() {
+            if reader.read_bit()? {
                 idx = self.tree[idx].left as usize;
             } else {
                 idx = self.tree[idx].right as usize;
             }            
         }
-        idx as u8        
+        Ok(idx as u8)
+    }
+
+    // A table may list each byte once, with a positive count, and the counts
+    // must add up without overflowing; every tree node's count is a partial
+    // sum of that total.
+    fn check_table(frequency_tbl: &[Freq]) -> Result<(), HuffmanError> {
+        let mut seen = [false; 256];
+        let mut total = 0usize;
+        for freq in frequency_tbl {
+            if seen[freq.byte as usize] || freq.count == 0 {
+                return Err(HuffmanError::BadTable);
+            }
+            seen[freq.byte as usize] = true;
+            total = total.checked_add(freq.count).ok_or(HuffmanError::BadTable)?;
+        }
+        Ok(())
     }
 }
 
@@ -274,21 +237,26 @@ impl Huffman {
         writer.data()
     }
 
-    pub fn decompress_data(&mut self, data: Vec<u8>, frequency_tbl: &[Freq]) -> Vec<u8> {
+    pub fn decompress_data(&mut self, data: Vec<u8>, frequency_tbl: &[Freq])
+                           -> Result<Vec<u8>, HuffmanError> {
+        Huffman::check_table(frequency_tbl)?;
         self.restore_tree(frequency_tbl);
 
         let mut reader = BitReader::new(&data);
         let mut out_byte: Vec<u8> = Vec::new();
         let root = self.root;
+        if root == -1 {
+            return Ok(out_byte);
+        }
         let byte_count = self.tree[root as usize].count;
 
         let mut i = 0;
         while i < byte_count {
-            let b = self.find_leaf_node(&mut reader, root);
+            let b = self.find_leaf_node(&mut reader, root)?;
             out_byte.push(b);
             i += 1;
         }
-        out_byte
+        Ok(out_byte)
     }
 }
 
@@ -296,37 +264,9 @@ impl Huffman {
 mod test {
     extern crate env_logger;
     
-    use super::*;
-    use std::iter;
-    
-    #[test]
-    fn test_read_bits() {
-        let data = vec![12u8];
-        let mut reader = BitReader::new(&data);
-
-        let results: Vec<_> = iter::repeat(0).map(|_| reader.read_bit()).take(8).collect();
-        assert_eq!(results, vec![false, false, false, false, true, true, false, false]);
-    }
-
-    #[test]
-    fn test_write_bits() {
-        let mut writer = BitWriter::new();
+    use rng::XorShift64;
 
-        for _ in 0..4 {
-            writer.write_bit(false);
-        }
-        for _ in 0..4 {
-            writer.write_bit(true);
-        }
-        for _ in 0..4 {
-            writer.write_bit(false);            
-        }
-        writer.write_bit(true);
-        writer.write_bit(true);
-        
-        writer.flush();
-        assert_eq!(writer.data(), vec![15u8, 12u8]);
-    }
+    use super::*;
 
     #[test]
     fn test_huffman() {        
@@ -338,9 +278,137 @@ mod test {
         let frequency_tbl = huffman1.frequency_tbl();
 
         let mut huffman2 = Huffman::new();
-        let decompressed_data = huffman2.decompress_data(compressed_data, &frequency_tbl);
+        let decompressed_data = huffman2.decompress_data(compressed_data, &frequency_tbl).unwrap();
 
         assert_eq!(s, String::from_utf8(decompressed_data).unwrap());
     }
-}
 
+    // Random data over a random alphabet size, skewed towards low bytes.
+    fn random_data(rng: &mut XorShift64) -> Vec<u8> {
+        let len = rng.next_below(2000);
+        let alphabet = 1 + rng.next_below(256);
+        (0..len)
+            .map(|_| {
+                let a = rng.next_below(alphabet);
+                let b = rng.next_below(alphabet);
+                a.min(b) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let mut huffman = Huffman::new();
+        let compressed = huffman.compress_data(Vec::new());
+        assert!(compressed.is_empty());
+        assert!(huffman.frequency_tbl().is_empty());
+        assert_eq!(Huffman::new().decompress_data(compressed, &[]), Ok(Vec::new()));
+
+        assert!(Huffman::new().compress_data_fast(&[]).is_empty());
+        assert_eq!(Huffman::new().decompress_data_fast(&[], &[]), Ok(Vec::new()));
+
+        let blob = Huffman::compress_to_vec(&[]);
+        assert_eq!(Huffman::decompress_from_slice(&blob), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_single_symbol() {
+        let data = vec![b'q'; 20];
+        let mut huffman = Huffman::new();
+        let compressed = huffman.compress_data(data.clone());
+        // One bit per byte.
+        assert_eq!(compressed.len(), 3);
+        let table = huffman.frequency_tbl();
+        assert_eq!(table.len(), 1);
+        assert_eq!(Huffman::new().decompress_data(compressed.clone(), &table), Ok(data.clone()));
+        assert_eq!(Huffman::new().decompress_data_fast(&compressed, &table), Ok(data.clone()));
+
+        let data = vec![255u8; 5];
+        let blob = Huffman::compress_to_vec(&data);
+        assert_eq!(Huffman::decompress_from_slice(&blob), Ok(data));
+    }
+
+    #[test]
+    fn test_truncated_data() {
+        let s = "so much words wow many compression";
+        let mut huffman = Huffman::new();
+        let compressed = huffman.compress_data(s.to_owned().into_bytes());
+        let table = huffman.frequency_tbl();
+
+        for len in 0..compressed.len() {
+            let prefix = compressed[..len].to_vec();
+            assert_eq!(Huffman::new().decompress_data(prefix.clone(), &table),
+                       Err(HuffmanError::Truncated));
+            assert_eq!(Huffman::new().decompress_data_fast(&prefix, &table),
+                       Err(HuffmanError::Truncated));
+        }
+    }
+
+    #[test]
+    fn test_bad_table() {
+        let dup = [Freq { byte: 1, count: 2 }, Freq { byte: 1, count: 3 }];
+        assert_eq!(Huffman::new().decompress_data(vec![0], &dup), Err(HuffmanError::BadTable));
+        let zero = [Freq { byte: 1, count: 0 }];
+        assert_eq!(Huffman::new().decompress_data_fast(&[0], &zero), Err(HuffmanError::BadTable));
+        let huge = [Freq { byte: 0, count: usize::MAX }, Freq { byte: 1, count: 1 }];
+        assert_eq!(Huffman::new().decompress_data(vec![0], &huge), Err(HuffmanError::BadTable));
+        assert_eq!(Huffman::new().decompress_data_fast(&[0], &huge), Err(HuffmanError::BadTable));
+    }
+
+    #[test]
+    fn test_bit_io_errors() {
+        use bitio::BitIoError;
+
+        assert_eq!(HuffmanError::from(BitIoError::UnexpectedEof), HuffmanError::Truncated);
+        assert_eq!(HuffmanError::from(BitIoError::Overflow), HuffmanError::CodeOverflow);
+    }
+
+    #[test]
+    fn test_random_round_trips() {
+        let mut rng = XorShift64::new(1);
+        for _ in 0..100 {
+            let data = random_data(&mut rng);
+
+            let mut huffman = Huffman::new();
+            let compressed = huffman.compress_data(data.clone());
+            let table = huffman.frequency_tbl();
+            assert_eq!(Huffman::new().decompress_data(compressed.clone(), &table).unwrap(), data);
+            assert_eq!(Huffman::new().decompress_data_fast(&compressed, &table).unwrap(), data);
+
+            let blob = Huffman::compress_to_vec(&data);
+            assert_eq!(Huffman::decompress_from_slice(&blob).unwrap(), data);
+            let canonical = CanonicalHuffman::compress(&data);
+            assert_eq!(CanonicalHuffman::decompress(&canonical).unwrap(), data);
+            let adaptive = AdaptiveHuffman::compress(&data);
+            assert_eq!(AdaptiveHuffman::decompress(&adaptive).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_random_corruption() {
+        let mut rng = XorShift64::new(2);
+        for _ in 0..100 {
+            let data = random_data(&mut rng);
+            let blob = Huffman::compress_to_vec(&data);
+
+            // Truncation anywhere must be reported, never panic.
+            let cut = rng.next_below(blob.len() as u64) as usize;
+            assert!(Huffman::decompress_from_slice(&blob[..cut]).is_err());
+
+            // A flipped bit either hits padding or is caught.
+            let mut corrupted = blob.clone();
+            let pos = rng.next_below(corrupted.len() as u64) as usize;
+            corrupted[pos] ^= 1 << rng.next_below(8);
+            if let Ok(decoded) = Huffman::decompress_from_slice(&corrupted) {
+                assert_eq!(decoded, data);
+            }
+
+            let canonical = CanonicalHuffman::compress(&data);
+            let mut corrupted = canonical.clone();
+            let pos = rng.next_below(corrupted.len() as u64) as usize;
+            corrupted[pos] ^= 1 << rng.next_below(8);
+            let _ = CanonicalHuffman::decompress(&corrupted);
+            let _ = AdaptiveHuffman::decompress(&corrupted);
+        }
+    }
+}
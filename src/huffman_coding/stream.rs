@@ -0,0 +1,295 @@
+// Streaming Huffman coding. Input is cut into blocks that are compressed
+// independently with their own canonical code, so memory use is bounded by
+// the block size no matter how long the stream is. Each block is written as
+// its compressed size (u32 LE) followed by `CanonicalHuffman::compress` output;
+// the stream ends at a block boundary.
+
+use std::io::{self, Read, Write};
+
+use super::CanonicalHuffman;
+
+pub const DEFAULT_BLOCK_SIZE: usize = 1 << 20;
+pub const MAX_BLOCK_SIZE: usize = 1 << 24;
+
+// Canonical header plus at most 15 bits per input byte.
+fn max_compressed_size(block_size: usize) -> usize {
+    8 + 128 + (block_size * 15).div_ceil(8)
+}
+
+pub struct HuffmanEncoder<W: Write> {
+    inner: Option<W>,
+    block: Vec<u8>,
+    block_size: usize,
+    // Compressed block the inner writer has not fully taken yet, and how
+    // much of it has been written. Kept so a failed write can be retried.
+    pending: Vec<u8>,
+    written: usize,
+}
+
+impl<W: Write> HuffmanEncoder<W> {
+    pub fn new(inner: W) -> HuffmanEncoder<W> {
+        HuffmanEncoder::with_block_size(inner, DEFAULT_BLOCK_SIZE)
+    }
+
+    pub fn with_block_size(inner: W, block_size: usize) -> HuffmanEncoder<W> {
+        assert!(block_size > 0 && block_size <= MAX_BLOCK_SIZE,
+                "block size must be in 1..={}", MAX_BLOCK_SIZE);
+        HuffmanEncoder {
+            inner: Some(inner),
+            block: Vec::with_capacity(block_size),
+            block_size,
+            pending: Vec::new(),
+            written: 0,
+        }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() && !self.block.is_empty() {
+            let compressed = CanonicalHuffman::compress(&self.block);
+            let size = compressed.len() as u32;
+            self.pending.extend_from_slice(&[size as u8, (size >> 8) as u8, (size >> 16) as u8, (size >> 24) as u8]);
+            self.pending.extend_from_slice(&compressed);
+            self.block.clear();
+        }
+        let inner = self.inner.as_mut().expect("encoder already finished");
+        while self.written < self.pending.len() {
+            match inner.write(&self.pending[self.written..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => self.written += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.pending.clear();
+        self.written = 0;
+        Ok(())
+    }
+
+    /// Writes out the last partial block and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_block()?;
+        let mut inner = self.inner.take().expect("encoder already finished");
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for HuffmanEncoder<W> {
+    /// Full blocks are only written out on the next call, before it accepts
+    /// anything, so an error always means that none of `buf` was taken.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.block.len() == self.block_size || !self.pending.is_empty() {
+            self.write_block()?;
+        }
+        let n = buf.len().min(self.block_size - self.block.len());
+        self.block.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    /// Ends the current block early so everything written so far reaches
+    /// the underlying writer.
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.as_mut().expect("encoder already finished").flush()
+    }
+}
+
+impl<W: Write> Drop for HuffmanEncoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_block();
+        }
+    }
+}
+
+pub struct HuffmanDecoder<R: Read> {
+    inner: R,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+    where E: Into<Box<dyn (::std::error::Error) + Send + Sync>>
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl<R: Read> HuffmanDecoder<R> {
+    pub fn new(inner: R) -> HuffmanDecoder<R> {
+        HuffmanDecoder {
+            inner,
+            block: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // Reads and decodes the next block. Returns false at a clean end of
+    // stream; running out of input inside a block is an error.
+    fn read_block(&mut self) -> io::Result<bool> {
+        let mut size = [0u8; 4];
+        let mut filled = 0;
+        while filled < 4 {
+            match self.inner.read(&mut size[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let size = size.iter().rev().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        if size > max_compressed_size(MAX_BLOCK_SIZE) {
+            return Err(invalid_data("huffman block too large"));
+        }
+
+        let mut compressed = vec![0u8; size];
+        self.inner.read_exact(&mut compressed)?;
+        if size < 8 {
+            return Err(invalid_data("huffman block header truncated"));
+        }
+        let count = compressed[..8].iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        if count > MAX_BLOCK_SIZE as u64 {
+            return Err(invalid_data("huffman block decodes to more than the maximum block size"));
+        }
+        self.block = CanonicalHuffman::decompress(&compressed).map_err(invalid_data)?;
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for HuffmanDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            if !self.read_block()? {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Cursor, Read, Write};
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| b"aaaabbbcc d\n"[(i * 7 + i / 13) % 12]).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = sample(100_000);
+        let mut encoder = HuffmanEncoder::with_block_size(Vec::new(), 4096);
+        for chunk in data.chunks(1000) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < data.len());
+
+        let mut decoder = HuffmanDecoder::new(Cursor::new(compressed));
+        let mut decoded = Vec::new();
+        decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_small_reads_and_flush() {
+        let mut encoder = HuffmanEncoder::new(Vec::new());
+        encoder.write_all(b"hello ").unwrap();
+        encoder.flush().unwrap();
+        encoder.write_all(b"world").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoder = HuffmanDecoder::new(&compressed[..]);
+        let mut decoded = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            let n = decoder.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            decoded.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(decoded, b"hello world");
+    }
+
+    #[test]
+    fn test_empty_stream() {
+        let compressed = HuffmanEncoder::new(Vec::new()).finish().unwrap();
+        assert!(compressed.is_empty());
+
+        let mut decoded = Vec::new();
+        HuffmanDecoder::new(&compressed[..]).read_to_end(&mut decoded).unwrap();
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let mut encoder = HuffmanEncoder::with_block_size(Vec::new(), 100);
+        encoder.write_all(&sample(1000)).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoded = Vec::new();
+        let err = HuffmanDecoder::new(&compressed[..compressed.len() - 10])
+            .read_to_end(&mut decoded)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // Fails its first `failures` writes and takes at most three bytes per call.
+    struct Flaky {
+        out: Vec<u8>,
+        failures: usize,
+    }
+
+    impl Write for Flaky {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(io::Error::other("flaky writer"));
+            }
+            let n = buf.len().min(3);
+            self.out.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_retry() {
+        let mut encoder = HuffmanEncoder::with_block_size(Flaky { out: Vec::new(), failures: 1 }, 4);
+        assert_eq!(encoder.write(b"abcd").unwrap(), 4);
+        // The full block goes out first; when that fails nothing is taken.
+        assert!(encoder.write(b"efgh").is_err());
+        assert_eq!(encoder.write(b"efgh").unwrap(), 4);
+        encoder.write_all(b"ijklmn").unwrap();
+        let compressed = encoder.finish().unwrap().out;
+
+        let mut decoded = Vec::new();
+        HuffmanDecoder::new(&compressed[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, b"abcdefghijklmn");
+    }
+
+    #[test]
+    fn test_oversized_block() {
+        let mut stream = vec![8 + 128, 0, 0, 0];
+        let count = MAX_BLOCK_SIZE as u64 + 1;
+        stream.extend((0..8).map(|i| (count >> (8 * i)) as u8));
+        stream.extend(vec![0x11; 128]);
+
+        let mut decoded = Vec::new();
+        let err = HuffmanDecoder::new(&stream[..]).read_to_end(&mut decoded).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,254 @@
+// Table-driven coding for `Huffman` trees. Encoding looks every symbol's code
+// up in a table built once per tree instead of climbing parent links per byte;
+// decoding resolves `LOOKUP_BITS` bits at a time through a lookup table and
+// only walks the tree for the rare codes longer than that.
+
+use bitio::{BitWriter, BitReader};
+
+use super::{Huffman, Freq, HuffmanError};
+
+const LOOKUP_BITS: u32 = 10;
+
+#[derive(Clone, Copy)]
+enum Entry {
+    // A code of `len <= LOOKUP_BITS` bits that decodes to `symbol`.
+    Leaf { symbol: u8, len: u32 },
+    // The lookup bits lead to this internal node; keep walking from there.
+    Node(usize),
+}
+
+/// Symbol to code map. Codes are read most significant bit first; a tree
+/// over bytes with `usize` counts is never deeper than 128 levels.
+pub struct EncodeTable {
+    codes: Vec<(u128, u32)>,
+}
+
+/// Lookup table borrowing the tree it was built from, which resolves the
+/// codes longer than the lookup width.
+pub struct DecodeTable<'a> {
+    huffman: &'a Huffman,
+    entries: Vec<Entry>,
+}
+
+impl Huffman {
+    fn is_leaf(&self, idx: usize) -> bool {
+        self.tree[idx].right == -1
+    }
+
+    pub fn encode_table(&self) -> EncodeTable {
+        let mut codes = vec![(0u128, 0u32); 256];
+        for (symbol, code) in codes.iter_mut().enumerate() {
+            if self.tree[symbol].count == 0 {
+                continue;
+            }
+            let mut bits = 0u128;
+            let mut len = 0;
+            let mut child = symbol as i32;
+            let mut parent = self.tree[symbol].parent;
+            while parent != -1 {
+                if self.tree[parent as usize].left == child {
+                    bits |= 1 << len;
+                }
+                len += 1;
+                child = parent;
+                parent = self.tree[parent as usize].parent;
+            }
+            *code = (bits, len);
+        }
+        EncodeTable { codes }
+    }
+
+    pub fn decode_table(&self) -> DecodeTable<'_> {
+        if self.root == -1 {
+            return DecodeTable { huffman: self, entries: Vec::new() };
+        }
+        let entries = (0..1usize << LOOKUP_BITS)
+            .map(|prefix| {
+                let mut node = self.root as usize;
+                let mut len = 0;
+                while len < LOOKUP_BITS && !self.is_leaf(node) {
+                    let bit = (prefix >> (LOOKUP_BITS - 1 - len)) & 1 == 1;
+                    node = if bit { self.tree[node].left } else { self.tree[node].right } as usize;
+                    len += 1;
+                }
+                if self.is_leaf(node) {
+                    Entry::Leaf { symbol: node as u8, len }
+                } else {
+                    Entry::Node(node)
+                }
+            })
+            .collect();
+        DecodeTable { huffman: self, entries }
+    }
+
+    /// Same output as `compress_data`, produced through an `EncodeTable`.
+    pub fn compress_data_fast(&mut self, data: &[u8]) -> Vec<u8> {
+        self.count_byte_freq(data);
+        self.build_tree();
+        self.encode_table().encode(data)
+    }
+
+    /// Decodes the output of `compress_data` through a `DecodeTable`.
+    pub fn decompress_data_fast(&mut self, data: &[u8], frequency_tbl: &[Freq])
+                                -> Result<Vec<u8>, HuffmanError> {
+        Huffman::check_table(frequency_tbl)?;
+        self.restore_tree(frequency_tbl);
+        if self.root == -1 {
+            return Ok(Vec::new());
+        }
+        let count = self.tree[self.root as usize].count;
+        self.decode_table().decode(data, count)
+    }
+}
+
+impl EncodeTable {
+    pub fn code(&self, symbol: u8) -> (u128, u32) {
+        self.codes[symbol as usize]
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        for &byte in data {
+            let (code, len) = self.codes[byte as usize];
+            if len > 64 {
+                writer.write_bits((code >> 64) as u64, len - 64);
+                writer.write_bits(code as u64, 64);
+            } else {
+                writer.write_bits(code as u64, len);
+            }
+        }
+        writer.into_bytes()
+    }
+}
+
+impl<'a> DecodeTable<'a> {
+    /// Decodes `count` symbols from `data`.
+    pub fn decode(&self, data: &[u8], count: usize) -> Result<Vec<u8>, HuffmanError> {
+        let huffman = self.huffman;
+        if self.entries.is_empty() && count > 0 {
+            return Err(HuffmanError::BadTable);
+        }
+        let mut reader = BitReader::new(data);
+        let mut out = Vec::new();
+        while out.len() < count {
+            match self.entries[reader.peek_bits(LOOKUP_BITS) as usize] {
+                Entry::Leaf { symbol, len } => {
+                    reader.skip_bits(len)?;
+                    out.push(symbol);
+                }
+                Entry::Node(mut node) => {
+                    reader.skip_bits(LOOKUP_BITS)?;
+                    while !huffman.is_leaf(node) {
+                        let next = if reader.read_bit()? {
+                            huffman.tree[node].left
+                        } else {
+                            huffman.tree[node].right
+                        };
+                        node = next as usize;
+                    }
+                    out.push(node as u8);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rng::XorShift64;
+    use test::Bencher;
+
+    fn sample_text(len: usize) -> Vec<u8> {
+        let words = ["the ", "quick ", "brown ", "fox ", "jumps ", "over ", "lazy ", "dog ",
+                     "huffman ", "table ", "decoder ", "\n"];
+        let mut rng = XorShift64::new(12345);
+        let mut out = Vec::with_capacity(len);
+        while out.len() < len {
+            out.extend_from_slice(words[rng.next_below(words.len() as u64) as usize].as_bytes());
+        }
+        out.truncate(len);
+        out
+    }
+
+    // Fibonacci counts make codes far longer than the lookup width.
+    fn skewed_data() -> Vec<u8> {
+        let mut counts = vec![1usize, 1];
+        while counts.len() < 20 {
+            let n = counts.len();
+            let next = counts[n - 1] + counts[n - 2];
+            counts.push(next);
+        }
+        let mut data = Vec::new();
+        for (symbol, &count) in counts.iter().enumerate() {
+            for _ in 0..count {
+                data.push(b'a' + symbol as u8);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_same_output_as_tree_coder() {
+        for data in &[sample_text(5000), skewed_data()] {
+            let slow = Huffman::new().compress_data(data.clone());
+            let mut huffman = Huffman::new();
+            let fast = huffman.compress_data_fast(data);
+            assert_eq!(slow, fast);
+
+            let table = huffman.frequency_tbl();
+            let decoded = Huffman::new().decompress_data_fast(&fast, &table).unwrap();
+            assert_eq!(&decoded, data);
+        }
+    }
+
+    #[test]
+    fn test_long_codes() {
+        let data = skewed_data();
+        let mut huffman = Huffman::new();
+        huffman.compress_data_fast(&data);
+        let (_, len) = huffman.encode_table().code(b'a');
+        assert!(len > LOOKUP_BITS);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = sample_text(1000);
+        let mut huffman = Huffman::new();
+        let compressed = huffman.compress_data_fast(&data);
+        let table = huffman.frequency_tbl();
+        assert_eq!(Huffman::new().decompress_data_fast(&compressed[..compressed.len() / 2], &table),
+                   Err(HuffmanError::Truncated));
+    }
+
+    #[bench]
+    fn bench_compress_tree(b: &mut Bencher) {
+        let data = sample_text(1 << 16);
+        b.iter(|| Huffman::new().compress_data(data.clone()));
+    }
+
+    #[bench]
+    fn bench_compress_table(b: &mut Bencher) {
+        let data = sample_text(1 << 16);
+        b.iter(|| Huffman::new().compress_data_fast(&data));
+    }
+
+    #[bench]
+    fn bench_decompress_tree(b: &mut Bencher) {
+        let data = sample_text(1 << 16);
+        let mut huffman = Huffman::new();
+        let compressed = huffman.compress_data(data);
+        let table = huffman.frequency_tbl();
+        b.iter(|| Huffman::new().decompress_data(compressed.clone(), &table).unwrap());
+    }
+
+    #[bench]
+    fn bench_decompress_table(b: &mut Bencher) {
+        let data = sample_text(1 << 16);
+        let mut huffman = Huffman::new();
+        let compressed = huffman.compress_data(data);
+        let table = huffman.frequency_tbl();
+        b.iter(|| Huffman::new().decompress_data_fast(&compressed, &table));
+    }
+}
@@ -12,9 +12,16 @@ mod stack;
 mod heap;
 mod queue;
 mod priority_queue;
+mod rng;
+mod bitio;
 mod huffman_coding;
+mod entropy_coding;
+mod deflate;
+mod suffix_array;
+mod bwt;
 mod insertion_sort;
 mod bsearch_tree;
+mod skip_list;
 mod binary_search;
 mod merge_sort;
 mod cnt_occurrences;
@@ -29,4 +36,7 @@ mod idtree;
 mod bitset;
 mod btree;
 mod segtree;
+mod fenwick;
+mod sparse_table;
+mod sqrt_decomposition;
 mod graph;
@@ -0,0 +1,71 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::super::heap::Heap;
+use super::super::queue::wait_timeout_while;
+
+/// Thread-safe priority queue: a single heap behind a mutex, with a condition
+/// variable so consumers can block until an element arrives. Share it between
+/// threads with an `Arc`; all operations take `&self`.
+pub struct ConcurrentPriorityQueue<T> {
+    heap: Mutex<Heap<T>>,
+    not_empty: Condvar,
+}
+
+impl<T: PartialOrd> ConcurrentPriorityQueue<T> {
+    pub fn new() -> ConcurrentPriorityQueue<T> {
+        ConcurrentPriorityQueue {
+            heap: Mutex::new(Heap::new()),
+            not_empty: Condvar::new(),
+        }
+    }
+
+    // A poisoned lock is recovered rather than propagated. No element is lost
+    // by a panic under the lock, but one raised by `PartialOrd` mid-sift can
+    // leave the heap out of order, and later dequeues may then return
+    // elements out of priority order.
+    fn lock(&self) -> MutexGuard<'_, Heap<T>> {
+        self.heap.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn count(&self) -> usize {
+        self.lock().count()
+    }
+
+    pub fn enqueue(&self, value: T) {
+        self.lock().insert(value);
+        self.not_empty.notify_one();
+    }
+
+    /// Removes the highest-priority element without blocking.
+    pub fn try_dequeue(&self) -> Option<T> {
+        self.lock().remove()
+    }
+
+    /// Blocks until an element is available and removes it.
+    pub fn dequeue(&self) -> T {
+        let mut heap = self.lock();
+        loop {
+            if let Some(value) = heap.remove() {
+                return value;
+            }
+            heap = self.not_empty.wait(heap).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Like `dequeue`, but gives up and returns `None` once `timeout` has
+    /// elapsed without an element becoming available.
+    pub fn dequeue_timeout(&self, timeout: Duration) -> Option<T> {
+        wait_timeout_while(&self.not_empty, self.lock(), timeout, |heap| heap.is_empty()).remove()
+    }
+}
+
+impl<T: PartialOrd + Clone> ConcurrentPriorityQueue<T> {
+    pub fn peek(&self) -> Option<T> {
+        self.lock().peek()
+    }
+}
@@ -1,5 +1,11 @@
 use super::heap::Heap;
 
+mod stable;
+mod concurrent;
+
+pub use self::stable::{StablePriorityQueue, BoundedPriorityQueue};
+pub use self::concurrent::ConcurrentPriorityQueue;
+
 pub struct PriorityQueue<T> {
     heap: Heap<T>,
 }
@@ -0,0 +1,141 @@
+use std::cmp::{Ordering, Reverse};
+
+use super::super::heap::Heap;
+
+// Wraps a value with its insertion sequence number. Among equal values the
+// one inserted earlier compares greater, so it leaves a max-heap first.
+struct Entry<T> {
+    value: T,
+    seq: u64,
+}
+
+impl<T: PartialOrd> PartialEq for Entry<T> {
+    fn eq(&self, other: &Entry<T>) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Entry<T>) -> Option<Ordering> {
+        match self.value.partial_cmp(&other.value) {
+            Some(Ordering::Equal) => Some(other.seq.cmp(&self.seq)),
+            ord => ord,
+        }
+    }
+}
+
+/// Priority queue that returns equal-priority items in insertion order.
+pub struct StablePriorityQueue<T> {
+    heap: Heap<Entry<T>>,
+    seq: u64,
+}
+
+impl<T: PartialOrd> StablePriorityQueue<T> {
+    pub fn new() -> StablePriorityQueue<T> {
+        StablePriorityQueue {
+            heap: Heap::new(),
+            seq: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn count(&self) -> usize {
+        self.heap.count()
+    }
+
+    pub fn enqueue(&mut self, value: T) {
+        let seq = self.seq;
+        self.seq += 1;
+        self.heap.insert(Entry { value, seq });
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.heap.remove().map(|entry| entry.value)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.top().map(|entry| &entry.value)
+    }
+}
+
+/// Keeps only the `capacity` greatest elements seen so far. Once full, a new
+/// element evicts the current worst one if it ranks higher; among equal
+/// elements the earlier insertion is kept.
+pub struct BoundedPriorityQueue<T> {
+    // Min-heap over the kept elements so the worst one is always on top.
+    heap: Heap<Reverse<Entry<T>>>,
+    capacity: usize,
+    seq: u64,
+}
+
+impl<T: PartialOrd> BoundedPriorityQueue<T> {
+    pub fn new(capacity: usize) -> BoundedPriorityQueue<T> {
+        BoundedPriorityQueue {
+            heap: Heap::new(),
+            capacity,
+            seq: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.heap.count() >= self.capacity
+    }
+
+    pub fn count(&self) -> usize {
+        self.heap.count()
+    }
+
+    /// Inserts `value`, returning whichever element no longer fits: the
+    /// evicted worst element, or `value` itself if it ranks no higher.
+    pub fn enqueue(&mut self, value: T) -> Option<T> {
+        let seq = self.seq;
+        self.seq += 1;
+        let entry = Reverse(Entry { value, seq });
+
+        if !self.is_full() {
+            self.heap.insert(entry);
+            return None;
+        }
+        let admit = match self.heap.top() {
+            Some(worst) => entry < *worst,
+            None => false,
+        };
+        if admit {
+            let evicted = self.heap.remove().map(|Reverse(e)| e.value);
+            self.heap.insert(entry);
+            evicted
+        } else {
+            Some((entry.0).value)
+        }
+    }
+
+    /// The element that would be evicted next.
+    pub fn peek_worst(&self) -> Option<&T> {
+        self.heap.top().map(|entry| &(entry.0).value)
+    }
+
+    pub fn dequeue_worst(&mut self) -> Option<T> {
+        self.heap.remove().map(|Reverse(entry)| entry.value)
+    }
+
+    /// Consumes the queue, returning the kept elements best first.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut out = Vec::with_capacity(self.heap.count());
+        while let Some(value) = self.dequeue_worst() {
+            out.push(value);
+        }
+        out.reverse();
+        out
+    }
+}
@@ -1,6 +1,9 @@
 use std::cmp::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-use super::PriorityQueue;
+use super::{PriorityQueue, StablePriorityQueue, BoundedPriorityQueue, ConcurrentPriorityQueue};
 
 #[derive(Clone, Debug, PartialEq)]
 struct Message {
@@ -97,3 +100,148 @@ fn test_out_of_order() {
     assert_eq!(queue.peek(), None);
 }
 
+
+#[test]
+fn test_stable_fifo_ties() {
+    let mut queue: StablePriorityQueue<Message> = StablePriorityQueue::new();
+
+    queue.enqueue(Message::new("a", 1));
+    queue.enqueue(Message::new("b", 2));
+    queue.enqueue(Message::new("c", 1));
+    queue.enqueue(Message::new("d", 2));
+    queue.enqueue(Message::new("e", 1));
+    queue.enqueue(Message::new("f", 2));
+
+    assert_eq!(queue.count(), 6);
+    assert_eq!(queue.peek(), Some(&Message::new("b", 2)));
+
+    let order: Vec<String> = (0..6).map(|_| queue.dequeue().unwrap().text).collect();
+    assert_eq!(order, vec!["b", "d", "f", "a", "c", "e"]);
+    assert!(queue.is_empty());
+    assert_eq!(queue.dequeue(), None);
+}
+
+#[test]
+fn test_stable_many_equal() {
+    let mut queue: StablePriorityQueue<Message> = StablePriorityQueue::new();
+    for i in 0..100 {
+        queue.enqueue(Message::new(&i.to_string(), 7));
+    }
+    for i in 0..100 {
+        assert_eq!(queue.dequeue(), Some(Message::new(&i.to_string(), 7)));
+    }
+}
+
+#[test]
+fn test_bounded_top_k() {
+    let mut queue: BoundedPriorityQueue<usize> = BoundedPriorityQueue::new(3);
+
+    assert!(queue.is_empty());
+    assert_eq!(queue.capacity(), 3);
+    assert_eq!(queue.enqueue(5), None);
+    assert_eq!(queue.enqueue(1), None);
+    assert_eq!(queue.enqueue(8), None);
+    assert!(queue.is_full());
+    assert_eq!(queue.peek_worst(), Some(&1));
+
+    assert_eq!(queue.enqueue(3), Some(1));
+    assert_eq!(queue.enqueue(2), Some(2));
+    assert_eq!(queue.enqueue(10), Some(3));
+    assert_eq!(queue.count(), 3);
+
+    assert_eq!(queue.into_sorted_vec(), vec![10, 8, 5]);
+}
+
+#[test]
+fn test_bounded_keeps_earliest_ties() {
+    let mut queue: BoundedPriorityQueue<Message> = BoundedPriorityQueue::new(2);
+
+    queue.enqueue(Message::new("a", 1));
+    queue.enqueue(Message::new("b", 1));
+    assert_eq!(queue.enqueue(Message::new("c", 1)), Some(Message::new("c", 1)));
+    assert_eq!(queue.enqueue(Message::new("d", 2)), Some(Message::new("b", 1)));
+
+    let kept: Vec<String> = queue.into_sorted_vec().into_iter().map(|m| m.text).collect();
+    assert_eq!(kept, vec!["d", "a"]);
+}
+
+#[test]
+fn test_bounded_zero_capacity() {
+    let mut queue: BoundedPriorityQueue<usize> = BoundedPriorityQueue::new(0);
+    assert_eq!(queue.enqueue(1), Some(1));
+    assert!(queue.is_empty());
+    assert_eq!(queue.dequeue_worst(), None);
+}
+
+#[test]
+fn test_concurrent_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ConcurrentPriorityQueue<Message>>();
+}
+
+#[test]
+fn test_concurrent_producers() {
+    let queue = Arc::new(ConcurrentPriorityQueue::new());
+
+    let producers: Vec<_> = (0..4)
+        .map(|t| {
+            let queue = queue.clone();
+            thread::spawn(move || for i in 0..250 {
+                queue.enqueue(t * 250 + i);
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+
+    assert_eq!(queue.count(), 1000);
+    assert_eq!(queue.peek(), Some(999));
+    for i in (0..1000).rev() {
+        assert_eq!(queue.try_dequeue(), Some(i));
+    }
+    assert!(queue.is_empty());
+    assert_eq!(queue.try_dequeue(), None);
+}
+
+#[test]
+fn test_concurrent_blocking_dequeue() {
+    let queue = Arc::new(ConcurrentPriorityQueue::new());
+
+    let consumers: Vec<_> = (0..4)
+        .map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || (0..100).map(|_| queue.dequeue()).sum::<usize>())
+        })
+        .collect();
+    for i in 0..400 {
+        queue.enqueue(i);
+    }
+
+    let total: usize = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+    assert_eq!(total, (0..400).sum());
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_concurrent_dequeue_timeout() {
+    let queue: Arc<ConcurrentPriorityQueue<usize>> = Arc::new(ConcurrentPriorityQueue::new());
+
+    assert_eq!(queue.dequeue_timeout(Duration::from_millis(10)), None);
+
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            queue.enqueue(42);
+        })
+    };
+    assert_eq!(queue.dequeue_timeout(Duration::from_secs(10)), Some(42));
+    producer.join().unwrap();
+
+    // Timeouts too long to add to `Instant::now()` must not panic.
+    queue.enqueue(7);
+    assert_eq!(queue.dequeue_timeout(Duration::MAX), Some(7));
+    queue.enqueue(8);
+    assert_eq!(queue.dequeue_timeout(Duration::from_secs(u64::MAX)), Some(8));
+}
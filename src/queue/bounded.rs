@@ -0,0 +1,115 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::Queue;
+
+/// Waits on `condvar` while `blocked` holds, for at most `timeout`, and
+/// hands the guard back either way. Any `Duration` is accepted: the time
+/// left is measured from the start rather than added to `Instant::now()`.
+pub fn wait_timeout_while<'a, T, F>(condvar: &Condvar, guard: MutexGuard<'a, T>,
+                                    timeout: Duration, blocked: F) -> MutexGuard<'a, T>
+    where F: FnMut(&mut T) -> bool
+{
+    condvar.wait_timeout_while(guard, timeout, blocked).unwrap_or_else(|e| e.into_inner()).0
+}
+
+/// Fixed-capacity FIFO shared between threads. `enqueue` blocks while the
+/// queue is full and `dequeue` blocks while it is empty; the `try_` variants
+/// fail immediately instead.
+pub struct BoundedQueue<T> {
+    inner: Mutex<Queue<T>>,
+    capacity: usize,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> BoundedQueue<T> {
+    pub fn new(capacity: usize) -> BoundedQueue<T> {
+        assert!(capacity > 0, "bounded queue capacity must be positive");
+        BoundedQueue {
+            inner: Mutex::new(Queue::with_capacity(capacity)),
+            capacity,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue<T>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn count(&self) -> usize {
+        self.lock().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.lock().count() == self.capacity
+    }
+
+    pub fn enqueue(&self, value: T) {
+        let mut queue = self.lock();
+        while queue.count() == self.capacity {
+            queue = self.not_full.wait(queue).unwrap_or_else(|e| e.into_inner());
+        }
+        queue.enqueue(value);
+        self.not_empty.notify_one();
+    }
+
+    /// Enqueues without blocking, handing `value` back if the queue is full.
+    pub fn try_enqueue(&self, value: T) -> Result<(), T> {
+        let mut queue = self.lock();
+        if queue.count() == self.capacity {
+            return Err(value);
+        }
+        queue.enqueue(value);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    pub fn enqueue_timeout(&self, value: T, timeout: Duration) -> Result<(), T> {
+        let capacity = self.capacity;
+        let mut queue = wait_timeout_while(&self.not_full, self.lock(), timeout,
+                                           |queue| queue.count() == capacity);
+        if queue.count() == capacity {
+            return Err(value);
+        }
+        queue.enqueue(value);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    pub fn dequeue(&self) -> T {
+        let mut queue = self.lock();
+        loop {
+            if let Some(value) = queue.dequeue() {
+                self.not_full.notify_one();
+                return value;
+            }
+            queue = self.not_empty.wait(queue).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    pub fn try_dequeue(&self) -> Option<T> {
+        let ret = self.lock().dequeue();
+        if ret.is_some() {
+            self.not_full.notify_one();
+        }
+        ret
+    }
+
+    pub fn dequeue_timeout(&self, timeout: Duration) -> Option<T> {
+        let ret = wait_timeout_while(&self.not_empty, self.lock(), timeout, |queue| queue.is_empty()).dequeue();
+        if ret.is_some() {
+            self.not_full.notify_one();
+        }
+        ret
+    }
+}
@@ -1,6 +1,19 @@
+use std::ops::{Index, IndexMut};
+
+mod bounded;
+mod spsc;
+mod mpmc;
+
+pub use self::bounded::{BoundedQueue, wait_timeout_while};
+pub use self::spsc::{spsc_queue, SpscProducer, SpscConsumer};
+pub use self::mpmc::MpmcQueue;
+
+// Growable ring buffer. `inner.len()` is the capacity; the `len` live
+// elements occupy the slots starting at `head`, wrapping around the end.
 pub struct Queue<T> {
     inner: Vec<Option<T>>,
     head: usize,
+    len: usize,
 }
 
 impl<T> Queue<T> {
@@ -8,47 +21,197 @@ impl<T> Queue<T> {
         Queue {
             inner: Vec::new(),
             head: 0,
+            len: 0,
         }
     }
 
+    pub fn with_capacity(capacity: usize) -> Queue<T> {
+        let mut queue = Queue::new();
+        queue.inner.resize_with(capacity, || None);
+        queue
+    }
+
     pub fn is_empty(&self) -> bool {
         self.count() == 0
     }
 
     pub fn count(&self) -> usize {
-        self.inner.len() - self.head            
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn slot(&self, idx: usize) -> usize {
+        (self.head + idx) % self.inner.len()
+    }
+
+    // Doubles the buffer, moving the wrapped-around prefix so the live
+    // elements stay contiguous modulo the new capacity.
+    fn grow(&mut self) {
+        let old_cap = self.inner.len();
+        let new_cap = if old_cap == 0 { 4 } else { old_cap * 2 };
+        self.inner.resize_with(new_cap, || None);
+        if self.head + self.len > old_cap {
+            let wrapped = self.head + self.len - old_cap;
+            for i in 0..wrapped {
+                self.inner.swap(i, old_cap + i);
+            }
+        }
     }
 
     pub fn enqueue(&mut self, value: T) {
-        self.inner.push(Some(value))
+        self.push_back(value)
     }
 
     pub fn dequeue(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        if self.len == self.inner.len() {
+            self.grow();
+        }
+        let slot = self.slot(self.len);
+        self.inner[slot] = Some(value);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        if self.len == self.inner.len() {
+            self.grow();
+        }
+        self.head = (self.head + self.inner.len() - 1) % self.inner.len();
+        self.inner[self.head] = Some(value);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
         if self.is_empty() {
             None
         } else {
             let ret = self.inner[self.head].take();
-            self.head += 1;
+            self.head = self.slot(1);
+            self.len -= 1;
+            ret
+        }
+    }
 
-            let percentage = self.head as f64 / self.inner.len() as f64;
-            if self.inner.len() > 50 && percentage > 0.25 {
-                let _ = self.inner.drain(..self.head);
-                self.head = 0;
-            }
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let slot = self.slot(self.len - 1);
+            self.len -= 1;
+            self.inner[slot].take()
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            self.get(self.len - 1)
+        }
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx < self.len {
+            self.inner[self.slot(idx)].as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx < self.len {
+            let slot = self.slot(idx);
+            self.inner[slot].as_mut()
+        } else {
+            None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+        self.head = 0;
+    }
+
+    /// Iterates from front to back.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            queue: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+}
+
+impl<T> Index<usize> for Queue<T> {
+    type Output = T;
+
+    fn index(&self, idx: usize) -> &T {
+        self.get(idx).expect("queue index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for Queue<T> {
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        self.get_mut(idx).expect("queue index out of bounds")
+    }
+}
+
+pub struct Iter<'a, T: 'a> {
+    queue: &'a Queue<T>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            None
+        } else {
+            let ret = self.queue.get(self.front);
+            self.front += 1;
             ret
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
 }
 
-impl<T: Clone> Queue<T> {
-    pub fn front(&self) -> Option<T> {
-        if self.is_empty() {
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
             None
         } else {
-            self.inner[self.head].clone()
+            self.back -= 1;
+            self.queue.get(self.back)
         }
     }
 }
 
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
 #[cfg(test)]
 mod test;
@@ -0,0 +1,130 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// `head` is only advanced by the consumer and `tail` only by the producer.
+// Both run over `0..2 * capacity` so that a full ring (`tail - head ==
+// capacity`) can be told apart from an empty one (`tail == head`); position
+// `i` lives at `buffer[i % capacity]`.
+struct Ring<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn advance(&self, idx: usize) -> usize {
+        if idx + 1 == 2 * self.capacity() { 0 } else { idx + 1 }
+    }
+
+    fn distance(&self, head: usize, tail: usize) -> usize {
+        (tail + 2 * self.capacity() - head) % (2 * self.capacity())
+    }
+
+    fn count(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        self.distance(head, tail)
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        let mut idx = head;
+        while idx != tail {
+            let slot = &mut self.buffer[idx % self.buffer.len()];
+            unsafe { slot.get_mut().as_mut_ptr().drop_in_place() };
+            idx = self.advance(idx);
+        }
+    }
+}
+
+/// Sending half of a lock-free single-producer/single-consumer ring queue.
+pub struct SpscProducer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// Receiving half of a lock-free single-producer/single-consumer ring queue.
+pub struct SpscConsumer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// Creates a fixed-capacity SPSC queue and returns its two halves. Each half
+/// can be moved to its own thread; neither operation ever blocks or locks.
+pub fn spsc_queue<T>(capacity: usize) -> (SpscProducer<T>, SpscConsumer<T>) {
+    assert!(capacity > 0, "spsc queue capacity must be positive");
+    let buffer: Vec<_> = (0..capacity)
+        .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+        .collect();
+    let ring = Arc::new(Ring {
+        buffer: buffer.into_boxed_slice(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (SpscProducer { ring: ring.clone() }, SpscConsumer { ring })
+}
+
+impl<T> SpscProducer<T> {
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    pub fn count(&self) -> usize {
+        self.ring.count()
+    }
+
+    /// Enqueues `value`, handing it back if the queue is full.
+    pub fn enqueue(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        let head = ring.head.load(Ordering::Acquire);
+        if ring.distance(head, tail) == ring.capacity() {
+            return Err(value);
+        }
+        // The slot is outside [head, tail), so the consumer will not touch it
+        // until the release store below publishes it.
+        let slot = &ring.buffer[tail % ring.capacity()];
+        unsafe { (*slot.get()).as_mut_ptr().write(value) };
+        ring.tail.store(ring.advance(tail), Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<T> SpscConsumer<T> {
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    pub fn count(&self) -> usize {
+        self.ring.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub fn dequeue(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        let tail = ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // The acquire load of `tail` makes the producer's write visible, and
+        // the producer will not reuse the slot until `head` moves past it.
+        let slot = &ring.buffer[head % ring.capacity()];
+        let value = unsafe { (*slot.get()).as_ptr().read() };
+        ring.head.store(ring.advance(head), Ordering::Release);
+        Some(value)
+    }
+}
@@ -1,4 +1,8 @@
-use super::Queue;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::{Queue, BoundedQueue, MpmcQueue, spsc_queue};
 
 #[test]
 fn test_empty() {
@@ -15,7 +19,7 @@ fn test_ops() {
     queue.enqueue(1);
     assert!(!queue.is_empty());
     assert_eq!(queue.count(), 1);
-    assert_eq!(queue.front(), Some(1));
+    assert_eq!(queue.front(), Some(&1));
     assert_eq!(queue.dequeue(), Some(1));
     assert!(queue.is_empty());
     assert_eq!(queue.count(), 0);
@@ -25,7 +29,7 @@ fn test_ops() {
         queue.enqueue(i);
     }
     assert_eq!(queue.count(), 100);
-    assert_eq!(queue.front(), Some(0));
+    assert_eq!(queue.front(), Some(&0));
 
     for i in 0..100 {
         assert_eq!(queue.dequeue(), Some(i));
@@ -35,3 +39,236 @@ fn test_ops() {
     assert!(queue.is_empty());
     assert_eq!(queue.dequeue(), None);
 }
+
+#[test]
+fn test_wrap_around() {
+    let mut queue: Queue<usize> = Queue::with_capacity(4);
+
+    for i in 0..3 {
+        queue.enqueue(i);
+    }
+    assert_eq!(queue.dequeue(), Some(0));
+    assert_eq!(queue.dequeue(), Some(1));
+    for i in 3..6 {
+        queue.enqueue(i);
+    }
+    assert_eq!(queue.capacity(), 4);
+    // Full and wrapped; the next enqueue has to grow the buffer.
+    queue.enqueue(6);
+    assert_eq!(queue.count(), 5);
+    assert_eq!(queue.iter().cloned().collect::<Vec<_>>(), vec![2, 3, 4, 5, 6]);
+    for i in 2..7 {
+        assert_eq!(queue.dequeue(), Some(i));
+    }
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_deque_ops() {
+    let mut queue: Queue<usize> = Queue::new();
+
+    assert_eq!(queue.front(), None);
+    assert_eq!(queue.back(), None);
+    assert_eq!(queue.pop_back(), None);
+
+    queue.push_back(2);
+    queue.push_front(1);
+    queue.push_back(3);
+    queue.push_front(0);
+    assert_eq!(queue.front(), Some(&0));
+    assert_eq!(queue.back(), Some(&3));
+    assert_eq!(queue.count(), 4);
+
+    assert_eq!(queue.pop_back(), Some(3));
+    assert_eq!(queue.pop_front(), Some(0));
+    assert_eq!(queue.pop_back(), Some(2));
+    assert_eq!(queue.pop_back(), Some(1));
+    assert_eq!(queue.pop_back(), None);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_index_and_iter() {
+    let mut queue: Queue<usize> = Queue::new();
+    for i in 0..10 {
+        queue.push_front(i);
+    }
+
+    assert_eq!(queue[0], 9);
+    assert_eq!(queue[9], 0);
+    assert_eq!(queue.get(10), None);
+    queue[0] = 100;
+    assert_eq!(queue.front(), Some(&100));
+
+    let forward: Vec<usize> = queue.iter().cloned().collect();
+    assert_eq!(forward, vec![100, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+    let backward: Vec<usize> = queue.iter().rev().cloned().collect();
+    assert_eq!(backward, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 100]);
+
+    let mut sum = 0;
+    for value in &queue {
+        sum += *value;
+    }
+    assert_eq!(sum, 136);
+    assert_eq!(queue.count(), 10);
+}
+
+#[test]
+#[should_panic]
+fn test_index_out_of_bounds() {
+    let mut queue: Queue<usize> = Queue::new();
+    queue.enqueue(1);
+    let _ = queue[1];
+}
+
+#[test]
+fn test_bounded_try_ops() {
+    let queue: BoundedQueue<usize> = BoundedQueue::new(2);
+
+    assert!(queue.is_empty());
+    assert_eq!(queue.try_enqueue(1), Ok(()));
+    assert_eq!(queue.try_enqueue(2), Ok(()));
+    assert!(queue.is_full());
+    assert_eq!(queue.try_enqueue(3), Err(3));
+    assert_eq!(queue.enqueue_timeout(3, Duration::from_millis(10)), Err(3));
+
+    assert_eq!(queue.try_dequeue(), Some(1));
+    assert_eq!(queue.dequeue_timeout(Duration::from_millis(10)), Some(2));
+    assert_eq!(queue.try_dequeue(), None);
+    assert_eq!(queue.dequeue_timeout(Duration::from_millis(10)), None);
+
+    // Timeouts too long to add to `Instant::now()` must not panic.
+    assert_eq!(queue.enqueue_timeout(4, Duration::MAX), Ok(()));
+    assert_eq!(queue.enqueue_timeout(5, Duration::from_secs(u64::MAX)), Ok(()));
+    assert_eq!(queue.dequeue_timeout(Duration::MAX), Some(4));
+    assert_eq!(queue.dequeue_timeout(Duration::from_secs(u64::MAX)), Some(5));
+}
+
+#[test]
+fn test_bounded_blocking() {
+    let queue = Arc::new(BoundedQueue::new(4));
+
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || for i in 0..1000 {
+            queue.enqueue(i);
+        })
+    };
+    for i in 0..1000 {
+        assert_eq!(queue.dequeue(), i);
+    }
+    producer.join().unwrap();
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_spsc() {
+    let (mut producer, mut consumer) = spsc_queue(3);
+
+    assert_eq!(consumer.dequeue(), None);
+    assert_eq!(producer.enqueue(1), Ok(()));
+    assert_eq!(producer.enqueue(2), Ok(()));
+    assert_eq!(producer.enqueue(3), Ok(()));
+    assert_eq!(producer.enqueue(4), Err(4));
+    assert_eq!(consumer.count(), 3);
+    assert_eq!(consumer.dequeue(), Some(1));
+    assert_eq!(producer.enqueue(4), Ok(()));
+    for i in 2..5 {
+        assert_eq!(consumer.dequeue(), Some(i));
+    }
+    assert!(consumer.is_empty());
+}
+
+#[test]
+fn test_spsc_threads() {
+    let (mut producer, mut consumer) = spsc_queue(16);
+
+    let handle = thread::spawn(move || for i in 0..10000 {
+        let mut value = i;
+        while let Err(v) = producer.enqueue(value) {
+            value = v;
+            thread::yield_now();
+        }
+    });
+    let mut expected = 0;
+    while expected < 10000 {
+        match consumer.dequeue() {
+            Some(value) => {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+    handle.join().unwrap();
+    assert_eq!(consumer.dequeue(), None);
+}
+
+#[test]
+fn test_spsc_drops_remaining() {
+    let value = Arc::new(());
+    {
+        let (mut producer, _consumer) = spsc_queue(4);
+        producer.enqueue(value.clone()).unwrap();
+        producer.enqueue(value.clone()).unwrap();
+        assert_eq!(Arc::strong_count(&value), 3);
+    }
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_mpmc() {
+    let queue: MpmcQueue<usize> = MpmcQueue::with_capacity(3);
+
+    assert_eq!(queue.capacity(), 4);
+    assert_eq!(queue.dequeue(), None);
+    for i in 0..4 {
+        assert_eq!(queue.enqueue(i), Ok(()));
+    }
+    assert_eq!(queue.enqueue(4), Err(4));
+    assert_eq!(queue.count(), 4);
+    for i in 0..4 {
+        assert_eq!(queue.dequeue(), Some(i));
+    }
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn test_mpmc_threads() {
+    let queue = Arc::new(MpmcQueue::with_capacity(64));
+
+    let producers: Vec<_> = (0..4)
+        .map(|t| {
+            let queue = queue.clone();
+            thread::spawn(move || for i in 0..1000 {
+                let mut value = t * 1000 + i;
+                while let Err(v) = queue.enqueue(value) {
+                    value = v;
+                    thread::yield_now();
+                }
+            })
+        })
+        .collect();
+    let consumers: Vec<_> = (0..4)
+        .map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut seen = Vec::new();
+                while seen.len() < 1000 {
+                    match queue.dequeue() {
+                        Some(value) => seen.push(value),
+                        None => thread::yield_now(),
+                    }
+                }
+                seen
+            })
+        })
+        .collect();
+
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    let mut all: Vec<usize> = consumers.into_iter().flat_map(|c| c.join().unwrap()).collect();
+    all.sort();
+    assert_eq!(all, (0..4000).collect::<Vec<_>>());
+}
@@ -0,0 +1,27 @@
+use super::XorShift64;
+
+#[test]
+fn test_deterministic() {
+    let mut a = XorShift64::new(42);
+    let mut b = XorShift64::new(42);
+    let mut c = XorShift64::new(43);
+    let xs: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+    assert_eq!(xs, (0..16).map(|_| b.next_u64()).collect::<Vec<_>>());
+    assert_ne!(xs, (0..16).map(|_| c.next_u64()).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_zero_seed() {
+    let mut rng = XorShift64::new(0);
+    assert!((0..16).any(|_| rng.next_u64() != 0));
+}
+
+#[test]
+fn test_next_below() {
+    let mut rng = XorShift64::new(1);
+    let mut seen = [false; 10];
+    for _ in 0..1000 {
+        seen[rng.next_below(10) as usize] = true;
+    }
+    assert!(seen.iter().all(|s| *s));
+}
@@ -0,0 +1,141 @@
+use super::ValueOp;
+
+/// A `ValueOp` with an identity element, i.e. a monoid.
+pub trait Monoid: ValueOp {
+    fn identity() -> Self;
+}
+
+/// Iterative segment tree stored in one flat vector. Leaves sit at
+/// `size..2 * size` for a power-of-two `size` with the unused tail padded
+/// by the identity, so the tree may be empty and can grow with `push`.
+pub struct FlatSegmentTree<T> {
+    tree: Vec<T>,
+    size: usize,
+    len: usize,
+}
+
+impl<T: Monoid + Clone> FlatSegmentTree<T> {
+    pub fn new(array: &[T]) -> FlatSegmentTree<T> {
+        let size = array.len().next_power_of_two();
+        let mut tree = vec![T::identity(); 2 * size];
+        tree[size..size + array.len()].clone_from_slice(array);
+        let mut tree = FlatSegmentTree {
+            tree,
+            size,
+            len: array.len(),
+        };
+        tree.rebuild();
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> &T {
+        assert!(index < self.len, "invalid index value");
+        &self.tree[self.size + index]
+    }
+
+    fn rebuild(&mut self) {
+        for node in (1..self.size).rev() {
+            self.pull(node);
+        }
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.tree[node] = ValueOp::op(self.tree[2 * node].clone(), self.tree[2 * node + 1].clone());
+    }
+
+    pub fn replace(&mut self, index: usize, item: T) {
+        assert!(index < self.len, "invalid index value");
+        let mut node = self.size + index;
+        self.tree[node] = item;
+        while node > 1 {
+            node /= 2;
+            self.pull(node);
+        }
+    }
+
+    /// Appends `item`, doubling the leaf capacity when it is exhausted.
+    pub fn push(&mut self, item: T) {
+        if self.len == self.size {
+            let size = 2 * self.size;
+            let mut tree = vec![T::identity(); 2 * size];
+            tree[size..size + self.len].clone_from_slice(&self.tree[self.size..self.size + self.len]);
+            self.tree = tree;
+            self.size = size;
+            self.rebuild();
+        }
+        self.len += 1;
+        let index = self.len - 1;
+        self.replace(index, item);
+    }
+
+    /// Combines the elements in `[left, right]`.
+    pub fn query(&self, left: usize, right: usize) -> T {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len, "invalid right bound");
+        let (mut left, mut right) = (left + self.size, right + self.size + 1);
+        let (mut left_val, mut right_val) = (T::identity(), T::identity());
+        while left < right {
+            if left & 1 == 1 {
+                left_val = ValueOp::op(left_val, self.tree[left].clone());
+                left += 1;
+            }
+            if right & 1 == 1 {
+                right -= 1;
+                right_val = ValueOp::op(self.tree[right].clone(), right_val);
+            }
+            left /= 2;
+            right /= 2;
+        }
+        ValueOp::op(left_val, right_val)
+    }
+
+    /// Combination of every element, the identity when empty.
+    pub fn query_all(&self) -> T {
+        self.tree[1].clone()
+    }
+
+    /// Smallest `end >= left` such that `pred` fails on the combination of
+    /// `[left, end]`, or `len()` if it holds up to the last element. `pred`
+    /// must hold for the identity and be monotone: once it fails on a range,
+    /// it fails on every extension of it.
+    pub fn partition_point<F: Fn(&T) -> bool>(&self, left: usize, pred: F) -> usize {
+        assert!(left <= self.len, "invalid left bound");
+        assert!(pred(&T::identity()), "predicate must hold for the identity");
+        if left == self.len {
+            return self.len;
+        }
+        let mut node = left + self.size;
+        let mut acc = T::identity();
+        loop {
+            while node & 1 == 0 {
+                node /= 2;
+            }
+            let next = ValueOp::op(acc.clone(), self.tree[node].clone());
+            if !pred(&next) {
+                // The answer lies under `node`; walk down towards it.
+                while node < self.size {
+                    node *= 2;
+                    let next = ValueOp::op(acc.clone(), self.tree[node].clone());
+                    if pred(&next) {
+                        acc = next;
+                        node += 1;
+                    }
+                }
+                return node - self.size;
+            }
+            acc = next;
+            node += 1;
+            if node.is_power_of_two() {
+                return self.len;
+            }
+        }
+    }
+}
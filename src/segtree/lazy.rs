@@ -0,0 +1,145 @@
+use super::ValueOp;
+
+/// A range update acting on `ValueOp` aggregates, e.g. "add 3" acting on
+/// sums or "assign 7" acting on minimums.
+pub trait LazyUpdate<T>: Copy {
+    /// The aggregate of `len` elements after each of them got this update.
+    fn apply(self, value: T, len: usize) -> T;
+
+    /// A single update equivalent to applying `older` and then `self`.
+    fn compose(self, older: Self) -> Self;
+}
+
+/// Segment tree supporting range updates alongside range queries, both in
+/// O(log n). Updates that cover a whole node are parked there and pushed
+/// down to the children only when a later update needs to look inside.
+pub struct LazySegmentTree<T, U> {
+    // Heap layout: node 1 is the root, node i has children 2i and 2i + 1.
+    values: Vec<T>,
+    pending: Vec<Option<U>>,
+    len: usize,
+}
+
+impl<T: Copy + ValueOp, U: LazyUpdate<T>> LazySegmentTree<T, U> {
+    pub fn new(array: &[T]) -> LazySegmentTree<T, U> {
+        assert!(!array.is_empty(), "segment tree needs at least one element");
+        let mut tree = LazySegmentTree {
+            values: vec![array[0]; 4 * array.len()],
+            pending: vec![None; 4 * array.len()],
+            len: array.len(),
+        };
+        tree.build(1, 0, array.len() - 1, array);
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn build(&mut self, node: usize, left: usize, right: usize, array: &[T]) {
+        if left == right {
+            self.values[node] = array[left];
+            return;
+        }
+        let middle = left + (right - left) / 2;
+        self.build(2 * node, left, middle, array);
+        self.build(2 * node + 1, middle + 1, right, array);
+        self.values[node] = ValueOp::op(self.values[2 * node], self.values[2 * node + 1]);
+    }
+
+    fn apply(&mut self, node: usize, len: usize, update: U) {
+        self.values[node] = update.apply(self.values[node], len);
+        self.pending[node] = Some(match self.pending[node] {
+            Some(older) => update.compose(older),
+            None => update,
+        });
+    }
+
+    fn push_down(&mut self, node: usize, left: usize, middle: usize, right: usize) {
+        if let Some(update) = self.pending[node].take() {
+            self.apply(2 * node, middle - left + 1, update);
+            self.apply(2 * node + 1, right - middle, update);
+        }
+    }
+
+    /// Combines the elements in `[left, right]`.
+    pub fn query(&self, left: usize, right: usize) -> T {
+        self.check_range(left, right);
+        self.query_at(1, 0, self.len - 1, left, right)
+    }
+
+    // Pending updates stay where they are; a partial answer from below is
+    // patched with this node's pending update on the way back up.
+    fn query_at(&self, node: usize, lo: usize, hi: usize, left: usize, right: usize) -> T {
+        if left == lo && right == hi {
+            return self.values[node];
+        }
+        let middle = lo + (hi - lo) / 2;
+        let value = if right <= middle {
+            self.query_at(2 * node, lo, middle, left, right)
+        } else if left > middle {
+            self.query_at(2 * node + 1, middle + 1, hi, left, right)
+        } else {
+            let left_val = self.query_at(2 * node, lo, middle, left, middle);
+            let right_val = self.query_at(2 * node + 1, middle + 1, hi, middle + 1, right);
+            ValueOp::op(left_val, right_val)
+        };
+        match self.pending[node] {
+            Some(update) => update.apply(value, right - left + 1),
+            None => value,
+        }
+    }
+
+    /// Applies `update` to every element in `[left, right]`.
+    pub fn update(&mut self, left: usize, right: usize, update: U) {
+        self.check_range(left, right);
+        let last = self.len - 1;
+        self.update_at(1, 0, last, left, right, update);
+    }
+
+    fn update_at(&mut self, node: usize, lo: usize, hi: usize, left: usize, right: usize, update: U) {
+        if left == lo && right == hi {
+            self.apply(node, hi - lo + 1, update);
+            return;
+        }
+        let middle = lo + (hi - lo) / 2;
+        self.push_down(node, lo, middle, hi);
+        if right <= middle {
+            self.update_at(2 * node, lo, middle, left, right, update);
+        } else if left > middle {
+            self.update_at(2 * node + 1, middle + 1, hi, left, right, update);
+        } else {
+            self.update_at(2 * node, lo, middle, left, middle, update);
+            self.update_at(2 * node + 1, middle + 1, hi, middle + 1, right, update);
+        }
+        self.values[node] = ValueOp::op(self.values[2 * node], self.values[2 * node + 1]);
+    }
+
+    pub fn replace(&mut self, index: usize, item: T) {
+        assert!(index < self.len, "invalid index value");
+        let (mut node, mut lo, mut hi) = (1, 0, self.len - 1);
+        let mut path = Vec::new();
+        while lo < hi {
+            let middle = lo + (hi - lo) / 2;
+            self.push_down(node, lo, middle, hi);
+            path.push(node);
+            if index <= middle {
+                node *= 2;
+                hi = middle;
+            } else {
+                node = 2 * node + 1;
+                lo = middle + 1;
+            }
+        }
+        self.values[node] = item;
+        self.pending[node] = None;
+        for &node in path.iter().rev() {
+            self.values[node] = ValueOp::op(self.values[2 * node], self.values[2 * node + 1]);
+        }
+    }
+
+    fn check_range(&self, left: usize, right: usize) {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len, "invalid right bound");
+    }
+}
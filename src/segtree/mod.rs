@@ -1,3 +1,9 @@
+mod lazy;
+mod flat;
+
+pub use self::lazy::{LazySegmentTree, LazyUpdate};
+pub use self::flat::{FlatSegmentTree, Monoid};
+
 enum SegTree<T> {
     Leaf(Node<T>),
     NotLeaf(Box<SegTree<T>>, Node<T>, Box<SegTree<T>>),
@@ -1,4 +1,5 @@
-use super::{SegmentTree, ValueOp};
+use super::{SegmentTree, ValueOp, LazySegmentTree, LazyUpdate, FlatSegmentTree, Monoid};
+use rng::XorShift64;
 use test::Bencher;
 
 impl ValueOp for usize {
@@ -7,6 +8,25 @@ impl ValueOp for usize {
     }
 }
 
+impl Monoid for usize {
+    fn identity() -> usize {
+        0
+    }
+}
+
+impl ValueOp for String {
+    fn op(mut self, rhs: String) -> String {
+        self.push_str(&rhs);
+        self
+    }
+}
+
+impl Monoid for String {
+    fn identity() -> String {
+        String::new()
+    }
+}
+
 #[test]
 fn test_query() {
     use std::iter;
@@ -33,6 +53,173 @@ fn test_replace() {
     assert_eq!(seg_tree.query(0, 0), 5);
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sum(i64);
+
+impl ValueOp for Sum {
+    fn op(self, rhs: Sum) -> Sum {
+        Sum(self.0 + rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Min(i64);
+
+impl ValueOp for Min {
+    fn op(self, rhs: Min) -> Min {
+        Min(self.0.min(rhs.0))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Add(i64);
+
+impl LazyUpdate<Sum> for Add {
+    fn apply(self, value: Sum, len: usize) -> Sum {
+        Sum(value.0 + self.0 * len as i64)
+    }
+
+    fn compose(self, older: Add) -> Add {
+        Add(self.0 + older.0)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Assign(i64);
+
+impl LazyUpdate<Min> for Assign {
+    fn apply(self, _: Min, _: usize) -> Min {
+        Min(self.0)
+    }
+
+    fn compose(self, _: Assign) -> Assign {
+        self
+    }
+}
+
+#[test]
+fn test_lazy_add_sum() {
+    let mut tree = LazySegmentTree::new(&[Sum(1), Sum(2), Sum(3), Sum(4), Sum(5)]);
+    assert_eq!(tree.query(0, 4), Sum(15));
+    tree.update(1, 3, Add(10));
+    assert_eq!(tree.query(0, 4), Sum(45));
+    assert_eq!(tree.query(2, 2), Sum(13));
+    tree.update(0, 2, Add(-1));
+    assert_eq!(tree.query(0, 1), Sum(11));
+    tree.replace(1, Sum(0));
+    assert_eq!(tree.query(1, 3), Sum(26));
+    assert_eq!(tree.query(0, 4), Sum(31));
+}
+
+#[test]
+fn test_lazy_assign_min() {
+    let mut tree = LazySegmentTree::new(&[Min(5), Min(3), Min(8), Min(6)]);
+    assert_eq!(tree.query(0, 3), Min(3));
+    tree.update(0, 2, Assign(7));
+    assert_eq!(tree.query(0, 3), Min(6));
+    tree.update(1, 1, Assign(2));
+    assert_eq!(tree.query(0, 0), Min(7));
+    assert_eq!(tree.query(1, 3), Min(2));
+    tree.update(0, 3, Assign(9));
+    assert_eq!(tree.query(1, 2), Min(9));
+}
+
+#[test]
+fn test_lazy_random() {
+    let mut rng = XorShift64::new(17);
+    let mut expected: Vec<i64> = (0..97).map(|_| rng.next_below(100) as i64).collect();
+    let init: Vec<_> = expected.iter().map(|&v| Sum(v)).collect();
+    let mut sums = LazySegmentTree::new(&init);
+    let init: Vec<_> = expected.iter().map(|&v| Min(v)).collect();
+    let mut mins = LazySegmentTree::new(&init);
+    let mut assigned = expected.clone();
+    for _ in 0..2000 {
+        let (a, b) = (rng.next_below(97) as usize, rng.next_below(97) as usize);
+        let (left, right) = (a.min(b), a.max(b));
+        let value = rng.next_below(200) as i64 - 100;
+        match rng.next_below(3) {
+            0 => {
+                sums.update(left, right, Add(value));
+                mins.update(left, right, Assign(value));
+                for i in left..right + 1 {
+                    expected[i] += value;
+                    assigned[i] = value;
+                }
+            },
+            1 => {
+                sums.replace(left, Sum(value));
+                mins.replace(left, Min(value));
+                expected[left] = value;
+                assigned[left] = value;
+            },
+            _ => {
+                assert_eq!(sums.query(left, right), Sum(expected[left..right + 1].iter().sum()));
+                assert_eq!(mins.query(left, right), Min(*assigned[left..right + 1].iter().min().unwrap()));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_flat_query() {
+    let mut tree: FlatSegmentTree<usize> = FlatSegmentTree::new(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(tree.query(0, 7), 36);
+    assert_eq!(tree.query(2, 5), 18);
+    assert_eq!(tree.query(1, 1), 2);
+    tree.replace(2, 10);
+    assert_eq!(tree.query(0, 4), 22);
+    assert_eq!(*tree.get(2), 10);
+    assert_eq!(tree.query_all(), 43);
+}
+
+#[test]
+fn test_flat_empty_and_push() {
+    let mut tree: FlatSegmentTree<usize> = FlatSegmentTree::new(&[]);
+    assert!(tree.is_empty());
+    assert_eq!(tree.query_all(), 0);
+    assert_eq!(tree.partition_point(0, |&sum| sum < 5), 0);
+    for i in 1..20 {
+        tree.push(i);
+        assert_eq!(tree.len(), i);
+        assert_eq!(tree.query(0, i - 1), i * (i + 1) / 2);
+        assert_eq!(tree.query_all(), i * (i + 1) / 2);
+    }
+    assert_eq!(tree.query(5, 9), 6 + 7 + 8 + 9 + 10);
+}
+
+#[test]
+fn test_flat_non_copy() {
+    let words: Vec<String> = ["a", "bc", "d", "ef", "g"].iter().map(|s| s.to_string()).collect();
+    let mut tree = FlatSegmentTree::new(&words);
+    assert_eq!(tree.query(0, 4), "abcdefg");
+    assert_eq!(tree.query(1, 3), "bcdef");
+    tree.replace(2, "XYZ".to_string());
+    assert_eq!(tree.query(1, 3), "bcXYZef");
+    tree.push("h".to_string());
+    assert_eq!(tree.query_all(), "abcXYZefgh");
+    assert_eq!(tree.partition_point(1, |s| s.len() <= 5), 3);
+}
+
+#[test]
+fn test_flat_partition_point() {
+    let array = [3, 1, 4, 1, 5, 9, 2, 6, 5];
+    let tree: FlatSegmentTree<usize> = FlatSegmentTree::new(&array);
+    for left in 0..array.len() + 1 {
+        for limit in 0..40 {
+            let mut sum = 0;
+            let mut expected = array.len();
+            for (i, value) in array.iter().enumerate().skip(left) {
+                sum += value;
+                if sum > limit {
+                    expected = i;
+                    break;
+                }
+            }
+            assert_eq!(tree.partition_point(left, |&sum| sum <= limit), expected);
+        }
+    }
+}
+
 #[bench]
 fn bench_query_segtree(b: &mut Bencher) {
     b.iter(|| {
@@ -44,3 +231,14 @@ fn bench_query_segtree(b: &mut Bencher) {
         }
     });
 }
+
+#[bench]
+fn bench_query_flat_segtree(b: &mut Bencher) {
+    b.iter(|| {
+        let array = vec![1; 100000];
+        let seg_tree: FlatSegmentTree<usize> = FlatSegmentTree::new(&array);
+        for i in 0..100000 - 1000 {
+            assert_eq!(seg_tree.query(i, i+1000), 1001);
+        }
+    });
+}
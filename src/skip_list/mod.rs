@@ -0,0 +1,312 @@
+use std::collections::Bound;
+use std::mem;
+use std::ops::{RangeBounds, RangeFull};
+
+use rng::XorShift64;
+
+pub const MAX_LEVEL: usize = 32;
+
+const DEFAULT_SEED: u64 = 0x5ca1ab1e;
+
+struct SkipNode<K, V> {
+    key: K,
+    value: V,
+    // Forward links, one per level the node takes part in.
+    next: Vec<Option<usize>>,
+}
+
+/// Ordered map backed by a skip list. Nodes live in a vector and link to
+/// each other by index; a node's height is drawn from a geometric
+/// distribution with p = 1/2, giving expected O(log n) search.
+pub struct SkipList<K, V> {
+    nodes: Vec<Option<SkipNode<K, V>>>,
+    free: Vec<usize>,
+    head: [Option<usize>; MAX_LEVEL],
+    // Number of levels currently in use.
+    level: usize,
+    len: usize,
+    rng: XorShift64,
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    pub fn new() -> SkipList<K, V> {
+        SkipList::with_seed(DEFAULT_SEED)
+    }
+
+    /// Builds an empty list whose node heights come from `seed`, so the
+    /// resulting layout is reproducible.
+    pub fn with_seed(seed: u64) -> SkipList<K, V> {
+        SkipList {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: [None; MAX_LEVEL],
+            level: 0,
+            len: 0,
+            rng: XorShift64::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn levels(&self) -> usize {
+        self.level
+    }
+
+    fn node(&self, n: usize) -> &SkipNode<K, V> {
+        self.nodes[n].as_ref().expect("dangling skip list link")
+    }
+
+    fn node_mut(&mut self, n: usize) -> &mut SkipNode<K, V> {
+        self.nodes[n].as_mut().expect("dangling skip list link")
+    }
+
+    // Successor of `node` (the head when `None`) on `level`.
+    fn next(&self, node: Option<usize>, level: usize) -> Option<usize> {
+        match node {
+            Some(n) => self.node(n).next[level],
+            None => self.head[level],
+        }
+    }
+
+    fn set_next(&mut self, node: Option<usize>, level: usize, to: Option<usize>) {
+        match node {
+            Some(n) => self.node_mut(n).next[level] = to,
+            None => self.head[level] = to,
+        }
+    }
+
+    // On every level, the last node whose key satisfies `before` (the head
+    // when there is none).
+    fn predecessors<F: Fn(&K) -> bool>(&self, before: F) -> [Option<usize>; MAX_LEVEL] {
+        let mut preds = [None; MAX_LEVEL];
+        let mut cur = None;
+        for level in (0..self.level).rev() {
+            while let Some(n) = self.next(cur, level) {
+                if !before(&self.node(n).key) {
+                    break;
+                }
+                cur = Some(n);
+            }
+            preds[level] = cur;
+        }
+        preds
+    }
+
+    fn find(&self, key: &K) -> Option<usize> {
+        let preds = self.predecessors(|k| k < key);
+        self.next(preds[0], 0).filter(|&n| self.node(n).key == *key)
+    }
+
+    fn random_level(&mut self) -> usize {
+        let level = 1 + self.rng.next_u64().trailing_ones() as usize;
+        level.min(MAX_LEVEL)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|n| &self.node(n).value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        match self.find(key) {
+            Some(n) => Some(&mut self.node_mut(n).value),
+            None => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut preds = self.predecessors(|k| *k < key);
+        if let Some(n) = self.next(preds[0], 0) {
+            if self.node(n).key == key {
+                return Some(mem::replace(&mut self.node_mut(n).value, value));
+            }
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for pred in &mut preds[self.level..level] {
+                *pred = None;
+            }
+            self.level = level;
+        }
+        let next = (0..level).map(|l| self.next(preds[l], l)).collect();
+        let node = SkipNode { key, value, next };
+        let n = match self.free.pop() {
+            Some(n) => {
+                self.nodes[n] = Some(node);
+                n
+            },
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        for (l, pred) in preds.iter().enumerate().take(level) {
+            self.set_next(*pred, l, Some(n));
+        }
+        self.len += 1;
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let preds = self.predecessors(|k| k < key);
+        let n = self.next(preds[0], 0).filter(|&n| self.node(n).key == *key)?;
+        let node = self.nodes[n].take().unwrap();
+        for (l, next) in node.next.into_iter().enumerate() {
+            self.set_next(preds[l], l, next);
+        }
+        self.free.push(n);
+        while self.level > 0 && self.head[self.level - 1].is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.head[0].map(|n| {
+            let node = self.node(n);
+            (&node.key, &node.value)
+        })
+    }
+
+    /// Iterates over the entries in ascending key order.
+    pub fn iter(&self) -> SkipRange<'_, K, V, RangeFull> {
+        self.range(..)
+    }
+
+    /// Ascending iteration over the entries whose keys fall within `range`.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> SkipRange<'_, K, V, R> {
+        let preds = match range.start_bound() {
+            Bound::Included(start) => self.predecessors(|k| k < start),
+            Bound::Excluded(start) => self.predecessors(|k| k <= start),
+            Bound::Unbounded => [None; MAX_LEVEL],
+        };
+        SkipRange {
+            list: self,
+            next: self.next(preds[0], 0),
+            range,
+        }
+    }
+
+    /// Panics unless every level is sorted, each level is a sub-list of the
+    /// one below and the node count matches `len`.
+    #[cfg(test)]
+    pub fn assert_invariants(&self) {
+        let mut below: Vec<usize> = Vec::new();
+        for level in 0..MAX_LEVEL {
+            let mut row = Vec::new();
+            let mut cur = self.head[level];
+            while let Some(n) = cur {
+                row.push(n);
+                cur = self.node(n).next[level];
+            }
+            assert!(row.windows(2).all(|w| self.node(w[0]).key < self.node(w[1]).key), "level out of order");
+            if level == 0 {
+                assert_eq!(row.len(), self.len);
+            } else {
+                assert!(row.iter().all(|n| below.contains(n)), "node missing from lower level");
+            }
+            assert_eq!(row.is_empty(), level >= self.level, "stale level count");
+            below = row;
+        }
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = SkipRange<'a, K, V, RangeFull>;
+
+    fn into_iter(self) -> SkipRange<'a, K, V, RangeFull> {
+        self.iter()
+    }
+}
+
+pub struct SkipRange<'a, K: 'a, V: 'a, R> {
+    list: &'a SkipList<K, V>,
+    next: Option<usize>,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>> Iterator for SkipRange<'a, K, V, R> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.list.node(self.next?);
+        let past_end = match self.range.end_bound() {
+            Bound::Included(end) => node.key > *end,
+            Bound::Excluded(end) => node.key >= *end,
+            Bound::Unbounded => false,
+        };
+        if past_end {
+            self.next = None;
+            return None;
+        }
+        self.next = node.next[0];
+        Some((&node.key, &node.value))
+    }
+}
+
+/// Ordered set on top of `SkipList`.
+pub struct SkipSet<T> {
+    map: SkipList<T, ()>,
+}
+
+impl<T: Ord> SkipSet<T> {
+    pub fn new() -> SkipSet<T> {
+        SkipSet { map: SkipList::new() }
+    }
+
+    pub fn with_seed(seed: u64) -> SkipSet<T> {
+        SkipSet { map: SkipList::with_seed(seed) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns `false` if the value was already present.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn iter(&self) -> SkipSetIter<'_, T> {
+        SkipSetIter(self.map.iter())
+    }
+}
+
+pub struct SkipSetIter<'a, T: 'a>(SkipRange<'a, T, (), RangeFull>);
+
+impl<'a, T: Ord> Iterator for SkipSetIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next().map(|(k, _)| k)
+    }
+}
+
+#[cfg(test)]
+mod test;
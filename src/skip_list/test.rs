@@ -0,0 +1,80 @@
+use std::collections::{BTreeMap, Bound};
+
+use super::*;
+
+#[test]
+fn test_basic() {
+    let mut list = SkipList::new();
+    assert!(list.is_empty());
+    assert_eq!(list.first(), None);
+    assert_eq!(list.remove(&3), None);
+
+    assert_eq!(list.insert(3, "c"), None);
+    assert_eq!(list.insert(1, "a"), None);
+    assert_eq!(list.insert(2, "b"), None);
+    assert_eq!(list.insert(2, "B"), Some("b"));
+    list.assert_invariants();
+
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.get(&2), Some(&"B"));
+    assert_eq!(list.get(&4), None);
+    *list.get_mut(&1).unwrap() = "A";
+    assert_eq!(list.first(), Some((&1, &"A")));
+    assert_eq!(list.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+               vec![(1, "A"), (2, "B"), (3, "c")]);
+
+    assert_eq!(list.remove(&1), Some("A"));
+    assert!(!list.contains_key(&1));
+    list.assert_invariants();
+    assert_eq!(list.first(), Some((&2, &"B")));
+}
+
+#[test]
+fn test_random_against_btree_map() {
+    let mut list = SkipList::with_seed(99);
+    let mut expected = BTreeMap::new();
+    let mut rng = XorShift64::new(5);
+    for round in 0..5000 {
+        let key = rng.next_below(400);
+        if rng.next_below(3) == 0 {
+            assert_eq!(list.remove(&key), expected.remove(&key));
+        } else {
+            assert_eq!(list.insert(key, round), expected.insert(key, round));
+        }
+    }
+    list.assert_invariants();
+    assert_eq!(list.len(), expected.len());
+    assert!(list.iter().eq(expected.iter()));
+    assert!(list.range(50..150).eq(expected.range(50..150)));
+    assert!(list.range(..=77).eq(expected.range(..=77)));
+    assert!(list.range((Bound::Excluded(10), Bound::Included(20)))
+            .eq(expected.range((Bound::Excluded(10), Bound::Included(20)))));
+    assert!(list.levels() <= 16);
+}
+
+#[test]
+fn test_seeded_layout_is_reproducible() {
+    let build = |seed| {
+        let mut list = SkipList::with_seed(seed);
+        for i in 0..200 {
+            list.insert(i, ());
+        }
+        list.levels()
+    };
+    assert_eq!(build(1), build(1));
+    assert_eq!(build(2), build(2));
+}
+
+#[test]
+fn test_skip_set() {
+    let mut set = SkipSet::with_seed(3);
+    assert!(set.insert(5));
+    assert!(set.insert(1));
+    assert!(!set.insert(5));
+    assert!(set.contains(&1));
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.iter().cloned().collect::<Vec<_>>(), vec![1, 5]);
+    assert!(set.remove(&1));
+    assert!(!set.remove(&1));
+    assert!(!set.is_empty());
+}
@@ -0,0 +1,50 @@
+use segtree::ValueOp;
+
+/// Marker for operations where `x.op(x) == x`, such as min, max or gcd.
+/// Overlapping ranges may then be combined without double counting.
+pub trait Idempotent: ValueOp {}
+
+/// Static range queries in O(1) after an O(n log n) build, for idempotent
+/// operations. The array cannot be changed once built.
+pub struct SparseTable<T> {
+    // `table[k][i]` combines the 2^k elements starting at i.
+    table: Vec<Vec<T>>,
+}
+
+impl<T: Idempotent + Clone> SparseTable<T> {
+    pub fn new(array: &[T]) -> SparseTable<T> {
+        let mut table = vec![array.to_vec()];
+        let mut width = 1;
+        while 2 * width <= array.len() {
+            let row = {
+                let prev = &table[table.len() - 1];
+                (0..prev.len() - width)
+                    .map(|i| ValueOp::op(prev[i].clone(), prev[i + width].clone()))
+                    .collect()
+            };
+            table.push(row);
+            width *= 2;
+        }
+        SparseTable { table }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Combines the elements in `[left, right]`.
+    pub fn query(&self, left: usize, right: usize) -> T {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len(), "invalid right bound");
+        let k = (usize::BITS - 1 - (right - left + 1).leading_zeros()) as usize;
+        let row = &self.table[k];
+        ValueOp::op(row[left].clone(), row[right + 1 - (1 << k)].clone())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,87 @@
+use super::{SparseTable, Idempotent};
+use rng::XorShift64;
+use segtree::ValueOp;
+use test::Bencher;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Min(i64);
+
+impl ValueOp for Min {
+    fn op(self, rhs: Min) -> Min {
+        Min(self.0.min(rhs.0))
+    }
+}
+
+impl Idempotent for Min {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Max(i64);
+
+impl ValueOp for Max {
+    fn op(self, rhs: Max) -> Max {
+        Max(self.0.max(rhs.0))
+    }
+}
+
+impl Idempotent for Max {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Gcd(u64);
+
+impl ValueOp for Gcd {
+    fn op(self, rhs: Gcd) -> Gcd {
+        let (mut a, mut b) = (self.0, rhs.0);
+        while b != 0 {
+            let t = a % b;
+            a = b;
+            b = t;
+        }
+        Gcd(a)
+    }
+}
+
+impl Idempotent for Gcd {}
+
+#[test]
+fn test_sparse_table() {
+    let array = [5, 2, 8, 6, 3, 7, 1, 4];
+    let mins = SparseTable::new(&array.iter().map(|&v| Min(v)).collect::<Vec<_>>());
+    let maxs = SparseTable::new(&array.iter().map(|&v| Max(v)).collect::<Vec<_>>());
+    assert_eq!(mins.len(), 8);
+    assert_eq!(mins.query(0, 7), Min(1));
+    assert_eq!(mins.query(0, 3), Min(2));
+    assert_eq!(mins.query(2, 2), Min(8));
+    assert_eq!(maxs.query(3, 6), Max(7));
+    assert_eq!(maxs.query(0, 7), Max(8));
+
+    let gcds = SparseTable::new(&[Gcd(12), Gcd(18), Gcd(24), Gcd(9), Gcd(27)]);
+    assert_eq!(gcds.query(0, 2), Gcd(6));
+    assert_eq!(gcds.query(0, 4), Gcd(3));
+    assert_eq!(gcds.query(3, 4), Gcd(9));
+
+    let empty: SparseTable<Min> = SparseTable::new(&[]);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_sparse_table_random() {
+    let mut rng = XorShift64::new(21);
+    let array: Vec<i64> = (0..300).map(|_| rng.next_below(1000) as i64).collect();
+    let mins = SparseTable::new(&array.iter().map(|&v| Min(v)).collect::<Vec<_>>());
+    for left in 0..array.len() {
+        for right in left..array.len() {
+            assert_eq!(mins.query(left, right), Min(*array[left..right + 1].iter().min().unwrap()));
+        }
+    }
+}
+
+#[bench]
+fn bench_query_sparse_table(b: &mut Bencher) {
+    b.iter(|| {
+        let array = vec![Min(1); 100000];
+        let table = SparseTable::new(&array);
+        for i in 0..100000 - 1000 {
+            assert_eq!(table.query(i, i+1000), Min(1));
+        }
+    });
+}
@@ -0,0 +1,62 @@
+use segtree::{Monoid, ValueOp};
+
+/// Splits the array into blocks of about sqrt(n) elements and keeps the
+/// combination of each block: point updates in O(sqrt n) and range
+/// queries in O(sqrt n).
+pub struct SqrtDecomposition<T> {
+    values: Vec<T>,
+    blocks: Vec<T>,
+    block_size: usize,
+}
+
+impl<T: Monoid + Clone> SqrtDecomposition<T> {
+    pub fn new(array: &[T]) -> SqrtDecomposition<T> {
+        let block_size = ((array.len() as f64).sqrt() as usize).max(1);
+        let blocks = array
+            .chunks(block_size)
+            .map(|chunk| chunk.iter().cloned().fold(T::identity(), ValueOp::op))
+            .collect();
+        SqrtDecomposition {
+            values: array.to_vec(),
+            blocks,
+            block_size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn replace(&mut self, index: usize, item: T) {
+        assert!(index < self.len(), "invalid index value");
+        self.values[index] = item;
+        let block = index / self.block_size;
+        let start = block * self.block_size;
+        let end = (start + self.block_size).min(self.len());
+        self.blocks[block] = self.fold(start, end);
+    }
+
+    fn fold(&self, start: usize, end: usize) -> T {
+        self.values[start..end].iter().cloned().fold(T::identity(), ValueOp::op)
+    }
+
+    /// Combines the elements in `[left, right]`.
+    pub fn query(&self, left: usize, right: usize) -> T {
+        assert!(left <= right, "left can't larger than right");
+        assert!(right < self.len(), "invalid right bound");
+        let (first, last) = (left / self.block_size, right / self.block_size);
+        if first == last {
+            return self.fold(left, right + 1);
+        }
+        let head = self.fold(left, (first + 1) * self.block_size);
+        let middle = self.blocks[first + 1..last].iter().cloned().fold(head, ValueOp::op);
+        ValueOp::op(middle, self.fold(last * self.block_size, right + 1))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,83 @@
+use super::SqrtDecomposition;
+use rng::XorShift64;
+use segtree::{Monoid, ValueOp};
+use test::Bencher;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sum(i64);
+
+impl ValueOp for Sum {
+    fn op(self, rhs: Sum) -> Sum {
+        Sum(self.0 + rhs.0)
+    }
+}
+
+impl Monoid for Sum {
+    fn identity() -> Sum {
+        Sum(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Concat(String);
+
+impl ValueOp for Concat {
+    fn op(self, rhs: Concat) -> Concat {
+        Concat(self.0 + &rhs.0)
+    }
+}
+
+impl Monoid for Concat {
+    fn identity() -> Concat {
+        Concat(String::new())
+    }
+}
+
+#[test]
+fn test_sqrt_decomposition() {
+    let mut sqrt = SqrtDecomposition::new(&[Sum(1), Sum(2), Sum(3), Sum(4), Sum(5), Sum(6), Sum(7), Sum(8)]);
+    assert_eq!(sqrt.query(0, 7), Sum(36));
+    assert_eq!(sqrt.query(2, 5), Sum(18));
+    assert_eq!(sqrt.query(1, 1), Sum(2));
+    sqrt.replace(2, Sum(10));
+    assert_eq!(sqrt.query(0, 4), Sum(22));
+
+    let letters: Vec<_> = "abcdefghij".chars().map(|c| Concat(c.to_string())).collect();
+    let mut sqrt = SqrtDecomposition::new(&letters);
+    assert_eq!(sqrt.query(0, 9), Concat("abcdefghij".to_string()));
+    assert_eq!(sqrt.query(2, 8), Concat("cdefghi".to_string()));
+    sqrt.replace(4, Concat("XY".to_string()));
+    assert_eq!(sqrt.query(3, 5), Concat("dXYf".to_string()));
+
+    let empty: SqrtDecomposition<Sum> = SqrtDecomposition::new(&[]);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_sqrt_decomposition_random() {
+    let mut rng = XorShift64::new(33);
+    let mut expected: Vec<i64> = (0..150).map(|_| rng.next_below(100) as i64).collect();
+    let mut sqrt = SqrtDecomposition::new(&expected.iter().map(|&v| Sum(v)).collect::<Vec<_>>());
+    for _ in 0..2000 {
+        let (a, b) = (rng.next_below(150) as usize, rng.next_below(150) as usize);
+        let (left, right) = (a.min(b), a.max(b));
+        if rng.next_below(2) == 1 {
+            let value = rng.next_below(100) as i64;
+            sqrt.replace(left, Sum(value));
+            expected[left] = value;
+        } else {
+            assert_eq!(sqrt.query(left, right), Sum(expected[left..right + 1].iter().sum()));
+        }
+    }
+}
+
+#[bench]
+fn bench_query_sqrt_decomposition(b: &mut Bencher) {
+    b.iter(|| {
+        let array = vec![Sum(1); 100000];
+        let sqrt = SqrtDecomposition::new(&array);
+        for i in 0..100000 - 1000 {
+            assert_eq!(sqrt.query(i, i+1000), Sum(1001));
+        }
+    });
+}
@@ -0,0 +1,59 @@
+use super::Stack;
+
+// Both stacks keep, for every depth, the index of the extreme element among
+// everything at or below that depth, so the query is a single lookup.
+macro_rules! extremum_stack {
+    ($name: ident, $query: ident, $replaces: expr) => {
+        pub struct $name<T> {
+            stack: Stack<T>,
+            extremes: Vec<usize>,
+        }
+
+        impl<T: PartialOrd> $name<T> {
+            pub fn new() -> $name<T> {
+                $name {
+                    stack: Stack::new(),
+                    extremes: Vec::new(),
+                }
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.stack.is_empty()
+            }
+
+            pub fn len(&self) -> usize {
+                self.stack.len()
+            }
+
+            pub fn push(&mut self, value: T) {
+                let idx = self.stack.len();
+                let extreme = match self.extremes.last() {
+                    Some(&current) if !$replaces(&value, &self.stack.inner[current]) => current,
+                    _ => idx,
+                };
+                self.stack.push(value);
+                self.extremes.push(extreme);
+            }
+
+            pub fn pop(&mut self) -> Option<T> {
+                self.extremes.pop();
+                self.stack.pop()
+            }
+
+            pub fn top(&self) -> Option<&T> {
+                self.stack.top()
+            }
+
+            pub fn $query(&self) -> Option<&T> {
+                self.extremes.last().map(|&idx| &self.stack.inner[idx])
+            }
+
+            pub fn iter(&self) -> ::std::iter::Rev<::std::slice::Iter<'_, T>> {
+                self.stack.iter()
+            }
+        }
+    }
+}
+
+extremum_stack!(MinStack, min, |value: &T, current: &T| value < current);
+extremum_stack!(MaxStack, max, |value: &T, current: &T| value > current);
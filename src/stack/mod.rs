@@ -1,3 +1,13 @@
+use std::iter::Rev;
+use std::slice;
+use std::vec;
+
+mod min_max;
+mod monotonic;
+
+pub use self::min_max::{MinStack, MaxStack};
+pub use self::monotonic::{next_greater, next_smaller};
+
 #[derive(Debug)]
 pub struct Stack<T> {
     inner: Vec<T>
@@ -32,12 +42,37 @@ impl<T> Stack<T> {
             Some(&self.inner[self.inner.len() - 1])
         }
     }
+
+    /// The element `n` positions below the top; `peek_n(0)` is the top.
+    pub fn peek_n(&self, n: usize) -> Option<&T> {
+        if n < self.inner.len() {
+            Some(&self.inner[self.inner.len() - 1 - n])
+        } else {
+            None
+        }
+    }
+
+    /// Iterates from top to bottom without popping.
+    pub fn iter(&self) -> Rev<slice::Iter<'_, T>> {
+        self.inner.iter().rev()
+    }
 }
 
-impl<T> Iterator for Stack<T> {
+impl<T> IntoIterator for Stack<T> {
     type Item = T;
-    fn next(&mut self) -> Option<T> {
-        self.inner.pop()
+    type IntoIter = Rev<vec::IntoIter<T>>;
+
+    fn into_iter(self) -> Rev<vec::IntoIter<T>> {
+        self.inner.into_iter().rev()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = Rev<slice::Iter<'a, T>>;
+
+    fn into_iter(self) -> Rev<slice::Iter<'a, T>> {
+        self.iter()
     }
 }
 
@@ -0,0 +1,34 @@
+use super::Stack;
+
+// Scans left to right with a stack of indices still waiting for an answer.
+// The stack stays monotonic, so each index is pushed and popped once.
+fn next_by<T, F>(values: &[T], resolves: F) -> Vec<Option<usize>>
+    where F: Fn(&T, &T) -> bool
+{
+    let mut result = vec![None; values.len()];
+    let mut pending: Stack<usize> = Stack::new();
+
+    for (idx, value) in values.iter().enumerate() {
+        while let Some(&top) = pending.top() {
+            if !resolves(value, &values[top]) {
+                break;
+            }
+            result[top] = Some(idx);
+            pending.pop();
+        }
+        pending.push(idx);
+    }
+    result
+}
+
+/// For every position, the index of the nearest element to its right that is
+/// strictly greater, or `None` if there is none.
+pub fn next_greater<T: PartialOrd>(values: &[T]) -> Vec<Option<usize>> {
+    next_by(values, |value, waiting| value > waiting)
+}
+
+/// For every position, the index of the nearest element to its right that is
+/// strictly smaller, or `None` if there is none.
+pub fn next_smaller<T: PartialOrd>(values: &[T]) -> Vec<Option<usize>> {
+    next_by(values, |value, waiting| value < waiting)
+}
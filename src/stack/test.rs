@@ -1,4 +1,4 @@
-use super::Stack;
+use super::{Stack, MinStack, MaxStack, next_greater, next_smaller};
 
 #[test]
 fn test_empty() {
@@ -48,3 +48,80 @@ fn test_two_element() {
     assert_eq!(stack.top(), None);
     assert!(stack.is_empty());
 }
+
+#[test]
+fn test_iter_does_not_consume() {
+    let mut stack: Stack<usize> = Stack::new();
+    for i in 0..4 {
+        stack.push(i);
+    }
+
+    assert_eq!(stack.iter().cloned().collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+    let mut sum = 0;
+    for value in &stack {
+        sum += *value;
+    }
+    assert_eq!(sum, 6);
+    assert_eq!(stack.len(), 4);
+
+    assert_eq!(stack.peek_n(0), Some(&3));
+    assert_eq!(stack.peek_n(3), Some(&0));
+    assert_eq!(stack.peek_n(4), None);
+
+    assert_eq!(stack.into_iter().collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+}
+
+#[test]
+fn test_min_stack() {
+    let mut stack: MinStack<usize> = MinStack::new();
+    assert_eq!(stack.min(), None);
+
+    stack.push(5);
+    stack.push(3);
+    stack.push(7);
+    stack.push(3);
+    stack.push(1);
+    assert_eq!(stack.len(), 5);
+    assert_eq!(stack.min(), Some(&1));
+    assert_eq!(stack.pop(), Some(1));
+    assert_eq!(stack.min(), Some(&3));
+    assert_eq!(stack.pop(), Some(3));
+    assert_eq!(stack.min(), Some(&3));
+    assert_eq!(stack.pop(), Some(7));
+    assert_eq!(stack.pop(), Some(3));
+    assert_eq!(stack.min(), Some(&5));
+    assert_eq!(stack.top(), Some(&5));
+    assert_eq!(stack.pop(), Some(5));
+    assert_eq!(stack.min(), None);
+    assert!(stack.is_empty());
+}
+
+#[test]
+fn test_max_stack() {
+    let mut stack: MaxStack<usize> = MaxStack::new();
+    assert_eq!(stack.max(), None);
+
+    for &value in &[2, 8, 4, 9, 1] {
+        stack.push(value);
+    }
+    assert_eq!(stack.iter().cloned().collect::<Vec<_>>(), vec![1, 9, 4, 8, 2]);
+    assert_eq!(stack.max(), Some(&9));
+    stack.pop();
+    assert_eq!(stack.max(), Some(&9));
+    stack.pop();
+    assert_eq!(stack.max(), Some(&8));
+    stack.pop();
+    stack.pop();
+    assert_eq!(stack.max(), Some(&2));
+}
+
+#[test]
+fn test_next_greater_smaller() {
+    let values = vec![2, 1, 2, 4, 3, 1];
+
+    assert_eq!(next_greater(&values),
+               vec![Some(3), Some(2), Some(3), None, None, None]);
+    assert_eq!(next_smaller(&values),
+               vec![Some(1), None, Some(5), Some(4), Some(5), None]);
+    assert_eq!(next_greater::<usize>(&[]), vec![]);
+}
@@ -0,0 +1,58 @@
+/// Suffix array of `s`: the starting positions of all suffixes in sorted
+/// order, a shorter suffix sorting before any longer one it prefixes.
+///
+/// Built by prefix doubling: after round `k` the suffixes are ranked by
+/// their first `2^k` bytes, and pairs of those ranks give the next round.
+pub fn suffix_array<T: Ord>(s: &[T]) -> Vec<usize> {
+    let n = s.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    sa.sort_by(|&a, &b| s[a].cmp(&s[b]));
+
+    let mut rank = vec![0usize; n];
+    for i in 1..n {
+        rank[sa[i]] = rank[sa[i - 1]] + (s[sa[i]] != s[sa[i - 1]]) as usize;
+    }
+
+    let mut k = 1;
+    let mut next = vec![0usize; n];
+    while k < n && rank[sa[n - 1]] < n - 1 {
+        // Rank 0 is reserved for "past the end", so shift real ranks by one.
+        let key = |i: usize| (rank[i], if i + k < n { rank[i + k] + 1 } else { 0 });
+        sa.sort_by_key(|&i| key(i));
+        next[sa[0]] = 0;
+        for i in 1..n {
+            next[sa[i]] = next[sa[i - 1]] + (key(sa[i]) != key(sa[i - 1])) as usize;
+        }
+        ::std::mem::swap(&mut rank, &mut next);
+        k *= 2;
+    }
+    sa
+}
+
+/// Longest common prefix of each suffix in `sa` with the one before it
+/// (Kasai's algorithm); `lcp[0]` is 0.
+pub fn lcp_array<T: Eq>(s: &[T], sa: &[usize]) -> Vec<usize> {
+    let n = s.len();
+    let mut rank = vec![0; n];
+    for (i, &p) in sa.iter().enumerate() {
+        rank[p] = i;
+    }
+    let mut lcp = vec![0; n];
+    let mut h = 0;
+    for p in 0..n {
+        if rank[p] == 0 {
+            h = 0;
+            continue;
+        }
+        let q = sa[rank[p] - 1];
+        while p + h < n && q + h < n && s[p + h] == s[q + h] {
+            h += 1;
+        }
+        lcp[rank[p]] = h;
+        h = h.saturating_sub(1);
+    }
+    lcp
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,42 @@
+use rng::XorShift64;
+
+use super::{suffix_array, lcp_array};
+
+fn naive(s: &[u8]) -> Vec<usize> {
+    let mut sa: Vec<usize> = (0..s.len()).collect();
+    sa.sort_by_key(|&i| &s[i..]);
+    sa
+}
+
+#[test]
+fn test_banana() {
+    let s = b"banana";
+    let sa = suffix_array(s);
+    assert_eq!(sa, vec![5, 3, 1, 0, 4, 2]);
+    assert_eq!(lcp_array(s, &sa), vec![0, 1, 3, 0, 0, 2]);
+}
+
+#[test]
+fn test_edge_cases() {
+    let empty: &[u8] = b"";
+    assert_eq!(suffix_array(empty), Vec::<usize>::new());
+    assert_eq!(suffix_array(b"x"), vec![0]);
+    assert_eq!(suffix_array(b"aaaa"), vec![3, 2, 1, 0]);
+    assert_eq!(suffix_array(&[3, 1, 2]), vec![1, 2, 0]);
+}
+
+#[test]
+fn test_against_naive() {
+    let mut rng = XorShift64::new(7);
+    for len in 0..200 {
+        let s: Vec<u8> = (0..len).map(|_| b'a' + rng.next_below(3) as u8).collect();
+        let sa = suffix_array(&s);
+        assert_eq!(sa, naive(&s));
+
+        let lcp = lcp_array(&s, &sa);
+        for i in 1..sa.len() {
+            let common = s[sa[i]..].iter().zip(&s[sa[i - 1]..]).take_while(|&(a, b)| a == b).count();
+            assert_eq!(lcp[i], common);
+        }
+    }
+}